@@ -1,6 +1,6 @@
-import { assetToPath, onEventShowMenu } from 'tauri-plugin-context-menu';
+import { onEventShowMenu } from 'tauri-plugin-context-menu';
 
-onEventShowMenu('contextmenu', async (_e:MouseEvent) => {
+onEventShowMenu('contextmenu', (_e:MouseEvent) => {
     const options = {
         theme: 'dark',
         items: [
@@ -13,7 +13,7 @@ onEventShowMenu('contextmenu', async (_e:MouseEvent) => {
                 payload: { message: "Hello from the payload!" },
                 shortcut: "alt+m",
                 icon: {
-                    path: await assetToPath('assets/16x16.png'),
+                    path: 'assets/16x16.png',
                     width: 32,
                     height: 32
                 }
@@ -1,7 +1,7 @@
 import * as tauriApi from '@tauri-apps/api';
 import * as tauriEvent from '@tauri-apps/api/event';
 import * as tauriApiPath from '@tauri-apps/api/path';
-import { assetToPath, showMenu, onEventShowMenu, ContextMenu } from './index';
+import { assetToPath, showMenu, hideMenu, updateMenu, createMenu, showCreatedMenu, destroyMenu, onEventShowMenu, registerShortcuts, unregisterShortcuts, setLocale, shortcutFromEvent, ContextMenu } from './index';
 
 jest.mock('@tauri-apps/api', () => ({
 	invoke: jest.fn()
@@ -38,10 +38,96 @@ describe('showMenu', () => {
 		expect(tauriEvent.listen).toHaveBeenCalledTimes(4); // events + menu-did-close
 	});
 
-	it('invokes tauriApi with the SHOW_COMMAND', () => {
-		showMenu({ items: [] });
+	it('invokes tauriApi with the SHOW_COMMAND', async () => {
+		await showMenu({ items: [] });
 		expect(tauriApi.invoke).toHaveBeenCalledWith(expect.stringMatching('plugin:context_menu|show_context_menu'), expect.any(Object));
 	});
+
+	it('sends the device pixel ratio with the position', async () => {
+		await showMenu({ pos: { x: 10, y: 20, units: 'css' }, items: [] });
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|show_context_menu', expect.objectContaining({
+			pos: { x: 10, y: 20, units: 'css', device_pixel_ratio: window.devicePixelRatio }
+		}));
+	});
+
+	it('opens keyboard menus below the focused element', async () => {
+		const button = document.createElement('button');
+		document.body.appendChild(button);
+		button.focus();
+		button.getBoundingClientRect = () => ({ left: 10, top: 20, bottom: 50, width: 80 } as DOMRect);
+
+		await showMenu({ trigger: 'keyboard', items: [] });
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|show_context_menu', expect.objectContaining({
+			trigger: 'keyboard',
+			pos: expect.objectContaining({ x: 10, y: 50, width: 80, height: 0, units: 'css' })
+		}));
+		button.remove();
+	});
+
+	it('passes keep_open_on_toggle to the plugin', async () => {
+		await showMenu({ keep_open_on_toggle: true, items: [{ label: 'Name', checked: true }] });
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|show_context_menu', expect.objectContaining({
+			keep_open_on_toggle: true,
+			items: [{ label: 'Name', checked: true }]
+		}));
+	});
+
+	it('removes the event listeners when the menu cannot be shown', async () => {
+		const unlisten = jest.fn();
+		(tauriEvent.listen as jest.Mock).mockResolvedValue(unlisten);
+		(tauriApi.invoke as jest.Mock).mockRejectedValueOnce('Invalid shortcut `ctrl+`: empty key name');
+
+		await expect(showMenu({ items: [{ event: jest.fn() }] })).rejects.toEqual('Invalid shortcut `ctrl+`: empty key name');
+		expect(unlisten).toHaveBeenCalledTimes(2); // item event + menu-did-close
+		(tauriEvent.listen as jest.Mock).mockReset();
+	});
+
+	it('loads lazy submenus when they are opened', async () => {
+		await showMenu({ items: [{ label: 'Branches', lazy: () => [{ label: 'main' }] }] });
+		const [eventName, handler] = (tauriEvent.listen as jest.Mock).mock.calls[0];
+		expect(eventName).toBe('root_context_menu_lazy_0');
+
+		await handler({ event: eventName, payload: { request: 3 } });
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|fill_submenu', { request: 3, items: [{ label: 'main' }] });
+	});
+});
+
+describe('hideMenu', () => {
+	it('invokes tauriApi with the HIDE_COMMAND', async () => {
+		await hideMenu();
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|hide_context_menu');
+	});
+});
+
+describe('updateMenu', () => {
+	it('invokes tauriApi with the updates', async () => {
+		const updates = [{ id: 'sync', label: 'Synced', disabled: false }];
+		await updateMenu(updates);
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|update_context_menu', { updates });
+	});
+});
+
+describe('createMenu', () => {
+	it('keeps the menu behind a handle until it is destroyed', async () => {
+		const unlisten = jest.fn();
+		(tauriEvent.listen as jest.Mock).mockResolvedValue(unlisten);
+		(tauriApi.invoke as jest.Mock).mockResolvedValueOnce(7);
+
+		const handle = await createMenu({ items: [{ id: 'sync', event: jest.fn() }] });
+		expect(handle).toBe(7);
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|create_menu', { items: [{ id: 'sync', event: expect.stringMatching(/^created_\w+_context_menu_item_0$/) }] });
+
+		await showCreatedMenu(handle, { x: 10, y: 20 });
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|show_menu', { handle: 7, pos: { x: 10, y: 20, device_pixel_ratio: window.devicePixelRatio }, trigger: undefined });
+
+		await updateMenu([{ id: 'sync', checked: true }], handle);
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|update_context_menu', { updates: [{ id: 'sync', checked: true }], handle: 7 });
+
+		await destroyMenu(handle);
+		expect(unlisten).toHaveBeenCalledTimes(1);
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|destroy_menu', { handle: 7 });
+		(tauriEvent.listen as jest.Mock).mockReset();
+	});
 });
 
 describe('onEventShowMenu', () => {
@@ -51,4 +137,58 @@ describe('onEventShowMenu', () => {
 		expect(addEventListenerSpy).toHaveBeenCalledWith('testEvent', expect.any(Function));
 		addEventListenerSpy.mockRestore();
 	});
-});
\ No newline at end of file
+});
+
+describe('shortcutFromEvent', () => {
+	it('uses the key of the layout and lists the modifiers', () => {
+		const e = new KeyboardEvent('keydown', { key: 'K', code: 'KeyK', ctrlKey: true, shiftKey: true });
+		expect(shortcutFromEvent(e)).toEqual('ctrl+shift+k');
+		// the A key of an AZERTY keyboard
+		expect(shortcutFromEvent(new KeyboardEvent('keydown', { key: 'q', code: 'KeyA', ctrlKey: true }))).toEqual('ctrl+q');
+	});
+
+	it('uses the physical key for shifted digits and non-latin letters', () => {
+		expect(shortcutFromEvent(new KeyboardEvent('keydown', { key: '!', code: 'Digit1', ctrlKey: true, shiftKey: true }))).toEqual('ctrl+shift+1');
+		expect(shortcutFromEvent(new KeyboardEvent('keydown', { key: 'ф', code: 'KeyA', ctrlKey: true }))).toEqual('ctrl+a');
+	});
+
+	it('maps named keys', () => {
+		expect(shortcutFromEvent(new KeyboardEvent('keydown', { key: 'Enter', code: 'Enter' }))).toEqual('return');
+		expect(shortcutFromEvent(new KeyboardEvent('keydown', { key: 'F5', code: 'F5', altKey: true }))).toEqual('alt+f5');
+		expect(shortcutFromEvent(new KeyboardEvent('keydown', { key: '7', code: 'Numpad7' }))).toEqual('num7');
+	});
+
+	it('ignores lone modifiers', () => {
+		expect(shortcutFromEvent(new KeyboardEvent('keydown', { key: 'Shift', code: 'ShiftLeft', shiftKey: true }))).toBeUndefined();
+	});
+});
+
+describe('registerShortcuts', () => {
+	it('registers the items and forwards key presses to the plugin', async () => {
+		(tauriApi.invoke as jest.Mock).mockResolvedValueOnce(['ctrl+c']);
+		await registerShortcuts('editor', [{ label: 'Copy', shortcut: 'cmd_or_ctrl+c', event: 'copy' }]);
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|register_shortcuts', { menu: 'editor', items: expect.any(Array) });
+
+		window.dispatchEvent(new KeyboardEvent('keydown', { key: 'c', code: 'KeyC', ctrlKey: true }));
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|trigger_shortcut', { shortcut: 'ctrl+c' });
+
+		// key presses of other shortcuts and typing are not forwarded
+		(tauriApi.invoke as jest.Mock).mockClear();
+		window.dispatchEvent(new KeyboardEvent('keydown', { key: 'v', code: 'KeyV', ctrlKey: true }));
+		const input = document.createElement('input');
+		document.body.appendChild(input);
+		input.dispatchEvent(new KeyboardEvent('keydown', { key: 'c', code: 'KeyC', ctrlKey: true, bubbles: true }));
+		input.remove();
+		expect(tauriApi.invoke).not.toHaveBeenCalled();
+
+		await unregisterShortcuts('editor');
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|unregister_shortcuts', { menu: 'editor' });
+	});
+});
+
+describe('setLocale', () => {
+	it('invokes tauriApi with the locale', async () => {
+		await setLocale('fr-CA');
+		expect(tauriApi.invoke).toHaveBeenCalledWith('plugin:context_menu|set_locale', { locale: 'fr-CA' });
+	});
+});
@@ -3,17 +3,69 @@ import * as tauriEvent from '@tauri-apps/api/event';
 import * as tauriApiPath from '@tauri-apps/api/path';
 
 const SHOW_COMMAND = 'plugin:context_menu|show_context_menu';
+const HIDE_COMMAND = 'plugin:context_menu|hide_context_menu';
+const UPDATE_COMMAND = 'plugin:context_menu|update_context_menu';
+const CREATE_COMMAND = 'plugin:context_menu|create_menu';
+const SHOW_CREATED_COMMAND = 'plugin:context_menu|show_menu';
+const DESTROY_COMMAND = 'plugin:context_menu|destroy_menu';
+const REGISTER_SHORTCUTS_COMMAND = 'plugin:context_menu|register_shortcuts';
+const UNREGISTER_SHORTCUTS_COMMAND = 'plugin:context_menu|unregister_shortcuts';
+const TRIGGER_SHORTCUT_COMMAND = 'plugin:context_menu|trigger_shortcut';
+const SET_LOCALE_COMMAND = 'plugin:context_menu|set_locale';
+const FILL_SUBMENU_COMMAND = 'plugin:context_menu|fill_submenu';
+
+// names of the non-character keys in shortcuts, by KeyboardEvent.key
+const NAMED_KEYS: { [key: string]: string } = {
+	' ': 'space',
+	'+': 'plus',
+	Tab: 'tab',
+	CapsLock: 'capslock',
+	NumLock: 'numlock',
+	ScrollLock: 'scrolllock',
+	Backspace: 'backspace',
+	Delete: 'delete',
+	Insert: 'insert',
+	Enter: 'return',
+	ArrowUp: 'up',
+	ArrowDown: 'down',
+	ArrowLeft: 'left',
+	ArrowRight: 'right',
+	Home: 'home',
+	End: 'end',
+	PageUp: 'pageup',
+	PageDown: 'pagedown',
+	Escape: 'escape'
+};
+
+// names of the numpad keys in shortcuts, by KeyboardEvent.code
+const NUMPAD_KEYS: { [code: string]: string } = {
+	NumpadDecimal: 'numdec',
+	NumpadAdd: 'numadd',
+	NumpadSubtract: 'numsub',
+	NumpadMultiply: 'nummult',
+	NumpadDivide: 'numdiv'
+};
+
+// unlisteners of the function events and key presses of the shortcuts of each registered menu
+const shortcutMenus = new Map<string, { unlisteners: tauriEvent.UnlistenFn[], shortcuts: string[] }>();
+
+// unlisteners of the function events of each created menu, by handle
+const createdMenus = new Map<number, tauriEvent.UnlistenFn[]>();
 
 import * as ContextMenu from './types';
 export type { ContextMenu };
 
+/**
+ * @deprecated Icon paths relative to the resource directory are now resolved by the plugin,
+ * pass them directly as `icon.path` instead.
+ */
 export async function assetToPath(asset: string): Promise<string> {
 	return await tauriApiPath.resolveResource(asset);
 }
 
 // for each item, if it is a function, replace it with an event listener
-async function processItems(items: ContextMenu.Item[], prefix: string): Promise<ContextMenu.ProcessResult> {
-	const unlisteners: tauriEvent.UnlistenFn[] = [];
+// (the listeners of lazy submenus are added to `unlisteners` once they are loaded)
+async function processItems(items: ContextMenu.Item[], prefix: string, unlisteners: tauriEvent.UnlistenFn[] = []): Promise<ContextMenu.ProcessResult> {
 
 	// Copy the items array so we don't mutate the original
 	// (needed if called multiple times)
@@ -39,10 +91,26 @@ async function processItems(items: ContextMenu.Item[], prefix: string): Promise<
 			processed[i].payload = undefined;
 		}
 
+		const itemLazy = processed[i].lazy;
+
+		if (typeof itemLazy === 'function') {
+			const eventName = `${prefix}_context_menu_lazy_${i}`;
+
+			// Load the submenu items when it is opened and send them back to the plugin
+			unlisteners.push(await tauriEvent.listen<ContextMenu.SubmenuRequest>(eventName, async (e) => {
+				const data:ContextMenu.CallbackEvent = { ...e, payload: items[i].payload };
+				const subitems = await itemLazy(data);
+				const result = await processItems(subitems, `${prefix}_${i}_lazy`, unlisteners);
+				await tauriApi.invoke(FILL_SUBMENU_COMMAND, { request: e.payload.request, items: result.processed });
+			}));
+
+			// Set the event name on the item instead of the function
+			processed[i].lazy = eventName;
+		}
+
 		// Recurse into subitems if they exist
 		if (items[i].subitems) {
-			const result = await processItems(items[i].subitems as ContextMenu.Item[], `${prefix}_${i}`);
-			unlisteners.push(...result.unlisteners);
+			const result = await processItems(items[i].subitems as ContextMenu.Item[], `${prefix}_${i}`, unlisteners);
 			processed[i].subitems = result.processed;
 		}
 	}
@@ -50,6 +118,17 @@ async function processItems(items: ContextMenu.Item[], prefix: string): Promise<
 	return { unlisteners, processed };
 }
 
+// the bottom edge of the focused element, where menus opened from the keyboard are shown
+function focusedElementPosition(): ContextMenu.Position | undefined {
+	const element = document.activeElement;
+	if (!element || element === document.body) {
+		return undefined;
+	}
+
+	const rect = element.getBoundingClientRect();
+	return { x: rect.left, y: rect.bottom, width: rect.width, height: 0, units: 'css' };
+}
+
 export async function showMenu(options: ContextMenu.Options) {
 	const { unlisteners, processed } = await processItems(options.items, 'root');
 
@@ -60,8 +139,59 @@ export async function showMenu(options: ContextMenu.Options) {
 		unlistenMenuClose();
 	});
 
-	// send the options to the plugin
-	tauriApi.invoke(SHOW_COMMAND, { ...options, items: processed } as any);
+	// send the options to the plugin, with the device pixel ratio used to convert CSS pixels
+	// menus opened from the keyboard have no pointer position to use
+	const requested = options.pos ?? (options.trigger === 'keyboard' ? focusedElementPosition() : undefined);
+	const pos = requested && { ...requested, device_pixel_ratio: window.devicePixelRatio };
+	try {
+		await tauriApi.invoke(SHOW_COMMAND, { ...options, pos, items: processed } as any);
+	} catch (error) {
+		// the menu was not shown (e.g. a shortcut is invalid)
+		unlisteners.forEach((unlistener) => unlistener());
+		unlistenMenuClose();
+		throw error;
+	}
+}
+
+// close the menu shown in the current window, menu-did-close is emitted with the `programmatic` reason
+export async function hideMenu() {
+	await tauriApi.invoke(HIDE_COMMAND);
+}
+
+// change the items with the given ids in the menu shown in the current window, without closing it
+// (or in the menu created with `handle`, shown or not)
+export async function updateMenu(updates: ContextMenu.ItemUpdate[], handle?: number) {
+	await tauriApi.invoke(UPDATE_COMMAND, handle === undefined ? { updates } : { updates, handle });
+}
+
+// build a menu once and keep it until it is destroyed, returns its handle
+export async function createMenu(options: ContextMenu.CreateOptions): Promise<number> {
+	// random, menus created before a page reload keep emitting events with their prefix
+	const id = Math.random().toString(36).slice(2);
+	const { unlisteners, processed } = await processItems(options.items, `created_${id}`);
+	try {
+		const handle: number = await tauriApi.invoke(CREATE_COMMAND, { ...options, items: processed } as any);
+		createdMenus.set(handle, unlisteners);
+		return handle;
+	} catch (error) {
+		unlisteners.forEach((unlistener) => unlistener());
+		throw error;
+	}
+}
+
+// show the menu created with `handle`, like `showMenu` does
+export async function showCreatedMenu(handle: number, pos?: ContextMenu.Position, trigger?: ContextMenu.Trigger) {
+	const requested = pos ?? (trigger === 'keyboard' ? focusedElementPosition() : undefined);
+	const position = requested && { ...requested, device_pixel_ratio: window.devicePixelRatio };
+	await tauriApi.invoke(SHOW_CREATED_COMMAND, { handle, pos: position, trigger });
+}
+
+// free the menu created with `handle`, closing it if it is shown
+export async function destroyMenu(handle: number) {
+	const unlisteners = createdMenus.get(handle);
+	createdMenus.delete(handle);
+	unlisteners?.forEach((unlistener) => unlistener());
+	await tauriApi.invoke(DESTROY_COMMAND, { handle });
 }
 
 export function onEventShowMenu(eventName: string, options: ContextMenu.EventOptions): void {
@@ -75,4 +205,93 @@ export function onEventShowMenu(eventName: string, options: ContextMenu.EventOpt
 
 		await showMenu(options);
 	});
+}
+
+// convert a keydown event to a shortcut string (e.g. `ctrl+shift+k`), undefined for lone modifiers
+export function shortcutFromEvent(e: KeyboardEvent): string | undefined {
+	let key: string | undefined;
+	if (/^Numpad[0-9]$/.test(e.code)) {
+		key = `num${e.code.charAt(6)}`;
+	} else if (NUMPAD_KEYS[e.code]) {
+		key = NUMPAD_KEYS[e.code];
+	} else if (/^[a-z0-9]$/i.test(e.key)) {
+		// use the key of the keyboard layout, lowercase as shift only changes its case
+		key = e.key.toLowerCase();
+	} else if (/^(Key[A-Z]|Digit[0-9])$/.test(e.code) && (e.shiftKey || e.key.length === 1 && e.key.toLowerCase() !== e.key.toUpperCase())) {
+		// shifted digits produce symbols and non-latin layouts other letters, use the physical key
+		key = e.code.charAt(e.code.length - 1).toLowerCase();
+	} else if (NAMED_KEYS[e.key]) {
+		key = NAMED_KEYS[e.key];
+	} else if (/^F([1-9]|1[0-9]|2[0-4])$/.test(e.key)) {
+		key = e.key.toLowerCase();
+	} else if (e.key.length === 1) {
+		key = e.key.toLowerCase();
+	}
+
+	if (!key) {
+		return undefined;
+	}
+
+	const modifiers: string[] = [];
+	if (e.ctrlKey) modifiers.push('ctrl');
+	if (e.altKey) modifiers.push('alt');
+	if (e.shiftKey) modifiers.push('shift');
+	if (e.metaKey) modifiers.push('meta');
+	return [ ...modifiers, key ].join('+');
+}
+
+// whether key presses on the element are typing (text fields and editable content)
+function isEditable(target: EventTarget | null): boolean {
+	return target instanceof Element && target.closest('input, textarea, select, [contenteditable]:not([contenteditable="false"])') !== null;
+}
+
+function onShortcutKeyDown(e: KeyboardEvent) {
+	if (isEditable(e.target)) {
+		return;
+	}
+
+	// only forward the key presses of registered shortcuts to the plugin
+	const shortcut = shortcutFromEvent(e);
+	if (shortcut && Array.from(shortcutMenus.values()).some((menu) => menu.shortcuts.includes(shortcut))) {
+		tauriApi.invoke(TRIGGER_SHORTCUT_COMMAND, { shortcut });
+	}
+}
+
+export async function registerShortcuts(menu: string, items: ContextMenu.Item[]) {
+	await unregisterShortcuts(menu);
+
+	const { unlisteners, processed } = await processItems(items, `shortcuts_${menu}`);
+	let shortcuts: string[];
+	try {
+		shortcuts = await tauriApi.invoke(REGISTER_SHORTCUTS_COMMAND, { menu, items: processed });
+	} catch (error) {
+		unlisteners.forEach((unlistener) => unlistener());
+		throw error;
+	}
+
+	// forward key presses to the plugin while any menu has registered shortcuts
+	if (shortcutMenus.size === 0) {
+		window.addEventListener('keydown', onShortcutKeyDown);
+	}
+	shortcutMenus.set(menu, { unlisteners, shortcuts });
+}
+
+export async function unregisterShortcuts(menu: string) {
+	const registered = shortcutMenus.get(menu);
+	if (!registered) {
+		return;
+	}
+
+	shortcutMenus.delete(menu);
+	registered.unlisteners.forEach((unlistener) => unlistener());
+	if (shortcutMenus.size === 0) {
+		window.removeEventListener('keydown', onShortcutKeyDown);
+	}
+
+	await tauriApi.invoke(UNREGISTER_SHORTCUTS_COMMAND, { menu });
+}
+
+// switch the locale used to resolve the label_key of the items
+export async function setLocale(locale: string) {
+	await tauriApi.invoke(SET_LOCALE_COMMAND, { locale });
 }
\ No newline at end of file
@@ -1,9 +1,22 @@
 import type { Event, UnlistenFn } from "@tauri-apps/api/event"
 
+export type Gravity = 'north_west' | 'north' | 'north_east' | 'west' | 'center' | 'east' | 'south_west' | 'south' | 'south_east'
+
+export type Units = 'css' | 'logical' | 'physical'
+
 export interface Position {
     x: number
     y: number
     is_absolute?: boolean
+    width?: number
+    height?: number
+    rect_anchor?: Gravity
+    menu_anchor?: Gravity
+    offset_x?: number
+    offset_y?: number
+    flip?: boolean
+    slide?: boolean
+    units?: Units
 }
 
 export interface Icon {
@@ -17,7 +30,10 @@ export interface CallbackEvent extends Event<unknown> {
 }
 
 export interface Item {
+    id?: string
     label?: string
+    label_key?: string
+    label_args?: Record<string, string|number>
     disabled?: boolean
     is_separator?: boolean
     event?: string|((e?:CallbackEvent) => any)
@@ -26,16 +42,53 @@ export interface Item {
     shortcut?: string
     icon?: Icon
     subitems?: Item[]
+    tooltip?: string
+    description?: string
+    markup?: string
+    badge?: string
+    lazy?: string|((e?:CallbackEvent) => Item[] | Promise<Item[]>)
+    keep_open_on_toggle?: boolean
+}
+
+export interface ItemUpdate {
+    id: string
+    label?: string
+    label_key?: string
+    label_args?: Record<string, string|number>
+    disabled?: boolean
+    checked?: boolean
+    icon?: Icon
+}
+
+export interface SubmenuRequest {
+    request: number
+    payload?: string
+}
+
+export type CloseReason = 'user' | 'programmatic'
+
+export interface MenuClosed {
+    reason: CloseReason
 }
 
 export type Theme = 'light' | 'dark'
 
+export type Direction = 'ltr' | 'rtl'
+
+export type Trigger = 'pointer' | 'keyboard'
+
 export interface Options {
     pos?: Position
     theme?: Theme
+    direction?: Direction
+    filter?: boolean
+    trigger?: Trigger
+    keep_open_on_toggle?: boolean
     items: Item[]
 }
 
+export type CreateOptions = Omit<Options, 'pos' | 'trigger'>
+
 export interface ProcessResult {
     unlisteners: UnlistenFn[]
     processed: Item[]
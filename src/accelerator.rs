@@ -0,0 +1,360 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::MenuItem;
+
+// Names of the non-character keys, as used in shortcut strings
+const NAMED_KEYS: [&str; 58] = [
+    "plus",
+    "space",
+    "tab",
+    "capslock",
+    "numlock",
+    "scrolllock",
+    "backspace",
+    "delete",
+    "insert",
+    "return",
+    "up",
+    "down",
+    "left",
+    "right",
+    "home",
+    "end",
+    "pageup",
+    "pagedown",
+    "escape",
+    "num0",
+    "num1",
+    "num2",
+    "num3",
+    "num4",
+    "num5",
+    "num6",
+    "num7",
+    "num8",
+    "num9",
+    "numdec",
+    "numadd",
+    "numsub",
+    "nummult",
+    "numdiv",
+    "f1",
+    "f2",
+    "f3",
+    "f4",
+    "f5",
+    "f6",
+    "f7",
+    "f8",
+    "f9",
+    "f10",
+    "f11",
+    "f12",
+    "f13",
+    "f14",
+    "f15",
+    "f16",
+    "f17",
+    "f18",
+    "f19",
+    "f20",
+    "f21",
+    "f22",
+    "f23",
+    "f24",
+];
+
+// Alternative names mapped to their entry in `NAMED_KEYS`
+const KEY_ALIASES: [(&str, &str); 2] = [("enter", "return"), ("esc", "escape")];
+
+/// A keyboard key that is not a modifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// A key producing a character, always stored lowercase.
+    Char(char),
+    /// A key without a character, identified by its name in shortcut strings (e.g. `f1`).
+    Named(&'static str),
+}
+
+/// Modifier keys held down together with the [`Key`] of an [`Accelerator`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers {
+    /// `cmd` or `cmd_or_ctrl`: Command on MacOS, Control elsewhere.
+    pub cmd_or_ctrl: bool,
+    pub ctrl: bool,
+    pub shift: bool,
+    /// `alt` or `opt`.
+    pub alt: bool,
+    pub alt_gr: bool,
+    /// `super` or `win`.
+    pub super_key: bool,
+    pub meta: bool,
+}
+
+/// A keyboard shortcut parsed from strings like `cmd_or_ctrl+shift+K`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Accelerator {
+    pub modifiers: Modifiers,
+    pub key: Key,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AcceleratorError {
+    /// A `+` without a key name on one of its sides.
+    EmptyToken,
+    /// A token that is neither a modifier, a named key nor a single character.
+    UnknownKey(String),
+    /// More than one non-modifier key.
+    DuplicateKey(String),
+    /// The same modifier given twice.
+    DuplicateModifier(String),
+    /// Only modifiers, without a key.
+    MissingKey,
+}
+
+impl fmt::Display for AcceleratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AcceleratorError::EmptyToken => write!(f, "empty key name"),
+            AcceleratorError::UnknownKey(key) => write!(f, "unknown key `{}`", key),
+            AcceleratorError::DuplicateKey(key) => {
+                write!(f, "more than one key, `{}` is not a modifier", key)
+            }
+            AcceleratorError::DuplicateModifier(modifier) => {
+                write!(f, "modifier `{}` is used more than once", modifier)
+            }
+            AcceleratorError::MissingKey => write!(f, "no key besides the modifiers"),
+        }
+    }
+}
+
+impl std::error::Error for AcceleratorError {}
+
+impl Accelerator {
+    /// Returns the accelerator with the modifiers that are the same key on the current OS merged,
+    /// so that two accelerators triggered by the same key presses compare equal.
+    pub fn normalized(mut self) -> Self {
+        let modifiers = &mut self.modifiers;
+        if cfg!(target_os = "macos") {
+            // `super`, `win` and `meta` are all the Command key
+            modifiers.cmd_or_ctrl |= modifiers.super_key || modifiers.meta;
+            modifiers.super_key = false;
+            modifiers.meta = false;
+        } else {
+            modifiers.ctrl |= modifiers.cmd_or_ctrl;
+            modifiers.cmd_or_ctrl = false;
+            // Webviews report the Super/Windows key as `meta`
+            modifiers.super_key |= modifiers.meta;
+            modifiers.meta = false;
+        }
+        self
+    }
+
+    /// Returns the key presses triggering the accelerator as the JS/TS package formats them
+    /// (e.g. `ctrl+shift+k`), with the modifiers in the order `ctrl`, `alt`, `shift` and `meta`.
+    pub fn key_press(self) -> String {
+        let modifiers = self.normalized().modifiers;
+        // The Command key on MacOS and the Super key elsewhere are reported as `meta`
+        let meta = if cfg!(target_os = "macos") {
+            modifiers.cmd_or_ctrl
+        } else {
+            modifiers.super_key
+        };
+        let names = [
+            (modifiers.ctrl, "ctrl"),
+            (modifiers.alt, "alt"),
+            (modifiers.alt_gr, "altgr"),
+            (modifiers.shift, "shift"),
+            (meta, "meta"),
+        ];
+
+        let mut key_press = String::new();
+        for (_, name) in names.iter().filter(|(active, _)| *active) {
+            key_press.push_str(name);
+            key_press.push('+');
+        }
+        match self.key {
+            Key::Char(c) => key_press.push(c),
+            Key::Named(name) => key_press.push_str(name),
+        }
+        key_press
+    }
+}
+
+impl FromStr for Accelerator {
+    type Err = AcceleratorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Modifiers::default();
+        let mut key = None;
+
+        for token in s.split('+') {
+            let token = token.trim();
+            if token.is_empty() {
+                return Err(AcceleratorError::EmptyToken);
+            }
+            let name = token.to_lowercase();
+
+            let modifier = match name.as_str() {
+                "cmd" | "cmd_or_ctrl" => Some(&mut modifiers.cmd_or_ctrl),
+                "ctrl" => Some(&mut modifiers.ctrl),
+                "shift" => Some(&mut modifiers.shift),
+                "alt" | "opt" => Some(&mut modifiers.alt),
+                "altgr" => Some(&mut modifiers.alt_gr),
+                "super" | "win" => Some(&mut modifiers.super_key),
+                "meta" => Some(&mut modifiers.meta),
+                _ => None,
+            };
+            if let Some(modifier) = modifier {
+                if *modifier {
+                    return Err(AcceleratorError::DuplicateModifier(token.to_string()));
+                }
+                *modifier = true;
+                continue;
+            }
+
+            if key.is_some() {
+                return Err(AcceleratorError::DuplicateKey(token.to_string()));
+            }
+            key = Some(parse_key(token, &name)?);
+        }
+
+        match key {
+            Some(key) => Ok(Accelerator { modifiers, key }),
+            None => Err(AcceleratorError::MissingKey),
+        }
+    }
+}
+
+impl fmt::Display for Accelerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.modifiers.cmd_or_ctrl, "cmd_or_ctrl"),
+            (self.modifiers.ctrl, "ctrl"),
+            (self.modifiers.shift, "shift"),
+            (self.modifiers.alt, "alt"),
+            (self.modifiers.alt_gr, "altgr"),
+            (self.modifiers.super_key, "super"),
+            (self.modifiers.meta, "meta"),
+        ];
+        for (_, name) in modifiers.iter().filter(|(active, _)| *active) {
+            write!(f, "{}+", name)?;
+        }
+        match self.key {
+            Key::Char(c) => write!(f, "{}", c),
+            Key::Named(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+fn parse_key(token: &str, name: &str) -> Result<Key, AcceleratorError> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(Key::Char(c));
+    }
+
+    let name = KEY_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, canonical)| canonical);
+    NAMED_KEYS
+        .iter()
+        .find(|named| **named == name)
+        .map(|named| Key::Named(named))
+        .ok_or_else(|| AcceleratorError::UnknownKey(token.to_string()))
+}
+
+/// Checks that the shortcuts of `items` and all their subitems can be parsed.
+pub fn validate_shortcuts(items: &[MenuItem]) -> Result<(), String> {
+    for item in items.iter() {
+        if let Some(shortcut) = &item.shortcut {
+            shortcut
+                .parse::<Accelerator>()
+                .map_err(|err| format!("Invalid shortcut `{}`: {}", shortcut, err))?;
+        }
+        if let Some(subitems) = &item.subitems {
+            validate_shortcuts(subitems)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_key() {
+        let accelerator: Accelerator = "cmd_or_ctrl+shift+K".parse().unwrap();
+        assert!(accelerator.modifiers.cmd_or_ctrl);
+        assert!(accelerator.modifiers.shift);
+        assert!(!accelerator.modifiers.alt);
+        assert_eq!(accelerator.key, Key::Char('k'));
+    }
+
+    #[test]
+    fn accepts_modifiers_in_any_order_and_key_aliases() {
+        let accelerator: Accelerator = "esc+opt".parse().unwrap();
+        assert!(accelerator.modifiers.alt);
+        assert_eq!(accelerator.key, Key::Named("escape"));
+        assert_eq!(accelerator.to_string(), "alt+escape");
+    }
+
+    #[test]
+    fn merges_the_modifiers_of_the_same_key() {
+        let cmd_or_ctrl: Accelerator = "cmd_or_ctrl+k".parse().unwrap();
+        let meta: Accelerator = "meta+k".parse().unwrap();
+        let ctrl: Accelerator = "ctrl+k".parse().unwrap();
+        let win: Accelerator = "win+k".parse().unwrap();
+
+        if cfg!(target_os = "macos") {
+            assert_eq!(cmd_or_ctrl.normalized(), meta.normalized());
+            assert_eq!(win.normalized(), meta.normalized());
+            assert_ne!(ctrl.normalized(), cmd_or_ctrl.normalized());
+        } else {
+            assert_eq!(cmd_or_ctrl.normalized(), ctrl.normalized());
+            assert_eq!(win.normalized(), meta.normalized());
+            assert_ne!(meta.normalized(), ctrl.normalized());
+        }
+    }
+
+    #[test]
+    fn formats_key_presses_like_the_js_package() {
+        let accelerator: Accelerator = "shift+cmd_or_ctrl+alt+Enter".parse().unwrap();
+        if cfg!(target_os = "macos") {
+            assert_eq!(accelerator.key_press(), "alt+shift+meta+return");
+        } else {
+            assert_eq!(accelerator.key_press(), "ctrl+alt+shift+return");
+        }
+
+        let accelerator: Accelerator = "win+f5".parse().unwrap();
+        assert_eq!(accelerator.key_press(), "meta+f5");
+    }
+
+    #[test]
+    fn reports_invalid_shortcuts() {
+        assert_eq!(
+            "ctrl+foo".parse::<Accelerator>(),
+            Err(AcceleratorError::UnknownKey("foo".to_string()))
+        );
+        assert_eq!(
+            "ctrl+a+b".parse::<Accelerator>(),
+            Err(AcceleratorError::DuplicateKey("b".to_string()))
+        );
+        assert_eq!(
+            "shift+shift+a".parse::<Accelerator>(),
+            Err(AcceleratorError::DuplicateModifier("shift".to_string()))
+        );
+        assert_eq!(
+            "ctrl++".parse::<Accelerator>(),
+            Err(AcceleratorError::EmptyToken)
+        );
+        assert_eq!("".parse::<Accelerator>(), Err(AcceleratorError::EmptyToken));
+        assert_eq!(
+            "ctrl+alt".parse::<Accelerator>(),
+            Err(AcceleratorError::MissingKey)
+        );
+    }
+}
@@ -0,0 +1,23 @@
+use serde::Serialize;
+
+/// Why a menu was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// The user picked an item or dismissed the menu.
+    User,
+    /// The menu was closed with `hide_context_menu`.
+    Programmatic,
+}
+
+/// Payload of the `menu-did-close` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct MenuClosed {
+    pub reason: CloseReason,
+}
+
+impl MenuClosed {
+    pub fn new(reason: CloseReason) -> Self {
+        Self { reason }
+    }
+}
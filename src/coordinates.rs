@@ -0,0 +1,173 @@
+use gtk::prelude::*;
+use serde::Serialize;
+
+use crate::position::Rect;
+use crate::Position;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+/// The windowing system GDK is running on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    X11,
+    Wayland,
+    Other,
+}
+
+impl Backend {
+    /// Detects the backend from the type of `display`, `GDK_BACKEND` is usually unset
+    /// and GDK picks the first backend that works.
+    pub fn detect(display: &gdk::Display) -> Self {
+        match display.type_().name() {
+            "GdkX11Display" => Backend::X11,
+            "GdkWaylandDisplay" => Backend::Wayland,
+            _ => Backend::Other,
+        }
+    }
+}
+
+/// What the position of a menu is relative to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Origin {
+    /// The top left corner of the webview, for positions relative to the window.
+    Webview,
+    /// The top left corner of the screen, for absolute positions.
+    Screen,
+    /// The window the menu is shown in, for the pointer position.
+    Window,
+}
+
+/// How the position of a menu was converted to the coordinates of the window it is shown in,
+/// reported in the `menu-did-open` event for debugging.
+#[derive(Debug, Clone, Serialize)]
+pub struct CoordinateMapping {
+    pub backend: Backend,
+    /// The position given to the plugin, or the pointer position.
+    pub input: Point,
+    pub relative_to: Origin,
+    /// Where the webview starts in the window, after the title bar and decorations.
+    pub webview_origin: Point,
+    /// Where the window starts on the screen, unknown on Wayland.
+    pub window_origin: Point,
+    /// The position in the coordinates of the window.
+    pub output: Point,
+}
+
+/// Finds the webview among `widget` and its children.
+pub fn find_webview(widget: &gtk::Widget) -> Option<gtk::Widget> {
+    if widget.type_().name() == "WebKitWebView" {
+        return Some(widget.clone());
+    }
+    widget
+        .downcast_ref::<gtk::Container>()?
+        .children()
+        .iter()
+        .find_map(find_webview)
+}
+
+/// Converts `pos` to the coordinates of the GDK window of `gtk_window`, which include
+/// the title bar and the shadows of client-side decorations.
+/// Uses the pointer position if `pos` is `None`.
+pub fn map_to_window(
+    gtk_window: &gtk::ApplicationWindow,
+    pos: Option<&Position>,
+) -> CoordinateMapping {
+    let gdk_window = gtk_window.window();
+    let backend = Backend::detect(&gtk_window.display());
+
+    // The webview is packed under the title bar (and menu bar), inside the decorations
+    let webview_origin = find_webview(gtk_window.upcast_ref())
+        .and_then(|webview| webview.translate_coordinates(gtk_window, 0, 0))
+        .map_or_else(Point::default, |(x, y)| Point::new(x as f64, y as f64));
+    // Wayland doesn't let clients know where their windows are, absolute positions are
+    // then taken relative to the window
+    let window_origin = match (backend, gdk_window.as_ref()) {
+        (Backend::Wayland, _) | (_, None) => Point::default(),
+        (_, Some(gdk_window)) => {
+            let (_, x, y) = gdk_window.origin();
+            Point::new(x as f64, y as f64)
+        }
+    };
+
+    let (input, relative_to) = match pos {
+        Some(position) if position.is_absolute.unwrap_or(false) => {
+            (Point::new(position.x, position.y), Origin::Screen)
+        }
+        Some(position) => (Point::new(position.x, position.y), Origin::Webview),
+        None => (pointer_position(gdk_window.as_ref()), Origin::Window),
+    };
+
+    let output = match relative_to {
+        Origin::Webview => Point::new(input.x + webview_origin.x, input.y + webview_origin.y),
+        Origin::Screen => Point::new(input.x - window_origin.x, input.y - window_origin.y),
+        Origin::Window => input,
+    };
+
+    CoordinateMapping {
+        backend,
+        input,
+        relative_to,
+        webview_origin,
+        window_origin,
+        output,
+    }
+}
+
+/// Moves `rect` inside of `gdk_window`, Wayland compositors only accept popups anchored
+/// to a rectangle within their parent surface.
+pub fn clamp_to_window(rect: &gdk::Rectangle, gdk_window: &gdk::Window) -> gdk::Rectangle {
+    let (window_width, window_height) = (gdk_window.width(), gdk_window.height());
+    let x = rect.x().max(0).min(window_width - 1);
+    let y = rect.y().max(0).min(window_height - 1);
+    gdk::Rectangle::new(
+        x,
+        y,
+        rect.width().min(window_width - x).max(0),
+        rect.height().min(window_height - y).max(0),
+    )
+}
+
+/// The work area (the monitor without panels and docks) of the monitor showing the
+/// `anchor` rectangle, in the coordinates of `gdk_window` starting at `window_origin`.
+pub fn work_area(gdk_window: &gdk::Window, window_origin: Point, anchor: &Rect) -> Option<Rect> {
+    let monitor = gdk_window.display().monitor_at_point(
+        (window_origin.x + anchor.x) as i32,
+        (window_origin.y + anchor.y) as i32,
+    )?;
+    let area = monitor.workarea();
+    Some(Rect::new(
+        area.x() as f64 - window_origin.x,
+        area.y() as f64 - window_origin.y,
+        area.width() as f64,
+        area.height() as f64,
+    ))
+}
+
+// The pointer position relative to `gdk_window`
+fn pointer_position(gdk_window: Option<&gdk::Window>) -> Point {
+    let pointer = gdk::Display::default()
+        .and_then(|display| display.default_seat())
+        .and_then(|seat| seat.pointer());
+    match (gdk_window, pointer) {
+        (Some(gdk_window), Some(pointer)) => {
+            let (_, x, y, _) = gdk_window.device_position(&pointer);
+            Point::new(x as f64, y as f64)
+        }
+        _ => {
+            eprintln!("Failed to get pointer position");
+            Point::default()
+        }
+    }
+}
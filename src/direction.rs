@@ -0,0 +1,30 @@
+use serde::Deserialize;
+
+// Languages written from right to left
+const RTL_LANGUAGES: [&str; 10] = ["ar", "ckb", "dv", "fa", "he", "ps", "sd", "ug", "ur", "yi"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn from_str(s: &str) -> Option<Direction> {
+        match s {
+            "ltr" => Some(Direction::Ltr),
+            "rtl" => Some(Direction::Rtl),
+            _ => None,
+        }
+    }
+
+    /// The direction of the language of `locale` (e.g. `ar-EG`).
+    pub fn from_locale(locale: &str) -> Direction {
+        let language = locale.split(&['-', '_'][..]).next().unwrap_or("");
+        if RTL_LANGUAGES.contains(&language.to_lowercase().as_str()) {
+            Direction::Rtl
+        } else {
+            Direction::Ltr
+        }
+    }
+}
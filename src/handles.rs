@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+
+static NEXT_HANDLE: AtomicU32 = AtomicU32::new(1);
+
+// Labels of the windows that created the menus, by handle.
+lazy_static::lazy_static! {
+    static ref HANDLES: Mutex<HashMap<u32, String>> = Mutex::new(HashMap::new());
+}
+
+/// Returns a new handle for a menu created by the window.
+pub fn reserve(window: &str) -> u32 {
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    HANDLES.lock().unwrap().insert(handle, window.to_string());
+    handle
+}
+
+/// Fails unless `handle` is a menu created by the window.
+pub fn check(window: &str, handle: u32) -> Result<(), String> {
+    match HANDLES.lock().unwrap().get(&handle) {
+        Some(label) if label == window => Ok(()),
+        _ => Err(format!("No menu with handle {}", handle)),
+    }
+}
+
+/// Releases `handle`, failing unless it is a menu created by the window.
+pub fn release(window: &str, handle: u32) -> Result<(), String> {
+    check(window, handle)?;
+    HANDLES.lock().unwrap().remove(&handle);
+    Ok(())
+}
+
+/// Releases the handles of all the menus created by the window and returns them.
+pub fn release_window(window: &str) -> Vec<u32> {
+    let mut handles = HANDLES.lock().unwrap();
+    let released: Vec<u32> = handles
+        .iter()
+        .filter(|(_, label)| label.as_str() == window)
+        .map(|(handle, _)| *handle)
+        .collect();
+    for handle in released.iter() {
+        handles.remove(handle);
+    }
+    released
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_the_window_of_the_handle() {
+        let handle = reserve("check");
+        assert_eq!(check("check", handle), Ok(()));
+        assert!(check("other", handle).is_err());
+        assert_ne!(reserve("check"), handle);
+    }
+
+    #[test]
+    fn releases_handles_once() {
+        let handle = reserve("release");
+        assert!(release("other", handle).is_err());
+        assert_eq!(release("release", handle), Ok(()));
+        assert!(release("release", handle).is_err());
+        assert!(check("release", handle).is_err());
+    }
+
+    #[test]
+    fn releases_the_handles_of_a_window() {
+        let first = reserve("closed");
+        let second = reserve("closed");
+        let kept = reserve("open");
+
+        let mut released = release_window("closed");
+        released.sort_unstable();
+        assert_eq!(released, [first, second]);
+        assert!(check("closed", first).is_err());
+        assert_eq!(check("open", kept), Ok(()));
+        assert!(release_window("closed").is_empty());
+    }
+}
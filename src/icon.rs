@@ -0,0 +1,239 @@
+use percent_encoding::percent_decode_str;
+use std::path::{Path, PathBuf};
+use tauri::PathResolver;
+
+use crate::scope::IconScope;
+use crate::MenuItem;
+
+// Prefixes produced by `convertFileSrc` for files on disk
+const ASSET_PREFIXES: [&str; 2] = ["asset://localhost/", "https://asset.localhost/"];
+
+// Prefixes of URLs of the app's own files, looked up in the resource directory
+const APP_PREFIXES: [&str; 2] = ["tauri://localhost/", "https://tauri.localhost/"];
+
+// Highest `@Nx` suffix looked up next to an icon file.
+const MAX_VARIANT_SCALE: u32 = 3;
+
+/// Returns the `@Nx` variant of the icon at `path` best suited for `scale_factor`,
+/// together with the scale of the selected file.
+///
+/// For `foo.png` on a 200% display this is `foo@2x.png` when that file exists.
+/// Missing variants fall back to the next lower scale and finally to `path` itself.
+pub fn scaled_variant(path: &str, scale_factor: f64) -> (PathBuf, u32) {
+    let original = Path::new(path);
+    let target = (scale_factor.ceil() as u32).clamp(1, MAX_VARIANT_SCALE);
+
+    if let (Some(stem), Some(parent)) = (original.file_stem(), original.parent()) {
+        for scale in (2..=target).rev() {
+            let mut file_name = stem.to_os_string();
+            file_name.push(format!("@{}x", scale));
+            if let Some(extension) = original.extension() {
+                file_name.push(".");
+                file_name.push(extension);
+            }
+
+            let candidate = parent.join(file_name);
+            if candidate.is_file() {
+                return (candidate, scale);
+            }
+        }
+    }
+
+    (original.to_path_buf(), 1)
+}
+
+/// Returns the scale of the icon at `path` from its `@Nx` suffix, 1 for files without one.
+#[cfg(any(target_os = "linux", test))]
+pub fn variant_scale(path: &Path) -> u32 {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.rsplit_once('@'))
+        .and_then(|(_, suffix)| suffix.strip_suffix('x'))
+        .and_then(|scale| scale.parse().ok())
+        .filter(|scale| (2..=MAX_VARIANT_SCALE).contains(scale))
+        .unwrap_or(1)
+}
+
+/// Resolves an icon path sent by the webview to a file on disk.
+///
+/// Accepts absolute paths, paths relative to the app's resource directory
+/// and `asset://` / `tauri://` URLs.
+pub fn resolve_path(path: &str, resolver: &PathResolver) -> PathBuf {
+    resolve_in(path, |relative| resolver.resolve_resource(relative))
+}
+
+// Resolves `path` with `resolve_resource` mapping relative paths to the resource directory
+fn resolve_in<F>(path: &str, resolve_resource: F) -> PathBuf
+where
+    F: Fn(&Path) -> Option<PathBuf>,
+{
+    for prefix in ASSET_PREFIXES.iter() {
+        if let Some(encoded) = path.strip_prefix(prefix) {
+            return PathBuf::from(decode(encoded).as_ref());
+        }
+    }
+
+    for prefix in APP_PREFIXES.iter() {
+        if let Some(encoded) = path.strip_prefix(prefix) {
+            let relative = PathBuf::from(decode(encoded).trim_start_matches('/'));
+            return resolve_resource(&relative).unwrap_or(relative);
+        }
+    }
+
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    resolve_resource(path).unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Rewrites the icon paths of `items` and all their subitems to the files that will be loaded,
+/// resolving them with [`resolve_path`] and picking the [`scaled_variant`] for `scale_factor`.
+///
+/// Fails for icons that are missing or outside of `scope`, with the same error for both.
+pub fn resolve_icons(
+    items: &mut [MenuItem],
+    resolver: &PathResolver,
+    scope: &IconScope,
+    scale_factor: f64,
+) -> Result<(), String> {
+    for item in items.iter_mut() {
+        if let Some(icon) = &mut item.icon {
+            let path = scope.check(&resolve_path(&icon.path, resolver))?;
+            // Only use a HiDPI variant if the scope allows it as well
+            let (variant, scale) = scaled_variant(&path.to_string_lossy(), scale_factor);
+            let path = match scope.check(&variant) {
+                Ok(variant) if scale > 1 => variant,
+                _ => path,
+            };
+            icon.path = path.to_string_lossy().into_owned();
+        }
+        if let Some(subitems) = &mut item.subitems {
+            resolve_icons(subitems, resolver, scope, scale_factor)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn decode(encoded: &str) -> std::borrow::Cow<'_, str> {
+    // Drop query strings and fragments the webview may have appended
+    let encoded = encoded.split(['?', '#']).next().unwrap_or("");
+    percent_decode_str(encoded).decode_utf8_lossy()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    // Creates an empty directory holding the given files
+    fn icon_dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("context-menu-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for file in files.iter() {
+            fs::write(dir.join(file), b"").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn resolves_relative_paths_in_the_resource_directory() {
+        let resources = |path: &Path| Some(Path::new("/app/resources").join(path));
+        assert_eq!(
+            resolve_in("icons/copy.png", resources),
+            PathBuf::from("/app/resources/icons/copy.png")
+        );
+        assert_eq!(
+            resolve_in("icons/copy.png", |_| None),
+            PathBuf::from("icons/copy.png")
+        );
+        assert_eq!(
+            resolve_in("/usr/share/icons/copy.png", resources),
+            PathBuf::from("/usr/share/icons/copy.png")
+        );
+    }
+
+    #[test]
+    fn decodes_asset_urls() {
+        let resources = |path: &Path| Some(Path::new("/app/resources").join(path));
+        assert_eq!(
+            resolve_in(
+                "asset://localhost/%2Fhome%2Fme%2Fmy%20icons%2Fcopy.png?v=2",
+                resources
+            ),
+            PathBuf::from("/home/me/my icons/copy.png")
+        );
+        assert_eq!(
+            resolve_in(
+                "https://asset.localhost/%2Fhome%2Fme%2Fcopy.png#top",
+                resources
+            ),
+            PathBuf::from("/home/me/copy.png")
+        );
+    }
+
+    #[test]
+    fn resolves_app_urls_in_the_resource_directory() {
+        let resources = |path: &Path| Some(Path::new("/app/resources").join(path));
+        assert_eq!(
+            resolve_in("tauri://localhost/icons/my%20copy.png?v=2", resources),
+            PathBuf::from("/app/resources/icons/my copy.png")
+        );
+        assert_eq!(
+            resolve_in("https://tauri.localhost/icons/copy.png", resources),
+            PathBuf::from("/app/resources/icons/copy.png")
+        );
+        // Never taken as an absolute path
+        assert_eq!(
+            resolve_in("tauri://localhost//etc/copy.png", resources),
+            PathBuf::from("/app/resources/etc/copy.png")
+        );
+    }
+
+    #[test]
+    fn picks_the_variant_matching_the_scale() {
+        let dir = icon_dir("exact", &["copy.png", "copy@2x.png", "copy@3x.png"]);
+        let path = dir.join("copy.png");
+        let path = path.to_str().unwrap();
+
+        assert_eq!(scaled_variant(path, 2.0), (dir.join("copy@2x.png"), 2));
+        assert_eq!(scaled_variant(path, 3.0), (dir.join("copy@3x.png"), 3));
+        // Fractional scales round up to the sharper variant
+        assert_eq!(scaled_variant(path, 1.5), (dir.join("copy@2x.png"), 2));
+        assert_eq!(scaled_variant(path, 1.0), (dir.join("copy.png"), 1));
+    }
+
+    #[test]
+    fn falls_back_to_a_lower_scale() {
+        let dir = icon_dir("lower", &["copy.png", "copy@2x.png"]);
+        let path = dir.join("copy.png");
+
+        assert_eq!(
+            scaled_variant(path.to_str().unwrap(), 4.0),
+            (dir.join("copy@2x.png"), 2)
+        );
+    }
+
+    #[test]
+    fn reads_the_scale_of_variants() {
+        assert_eq!(variant_scale(Path::new("/icons/open@2x.png")), 2);
+        assert_eq!(variant_scale(Path::new("/icons/open@3x")), 3);
+        assert_eq!(variant_scale(Path::new("/icons/open.png")), 1);
+        assert_eq!(variant_scale(Path::new("/icons/me@home.png")), 1);
+        assert_eq!(variant_scale(Path::new("/icons/open@9x.png")), 1);
+    }
+
+    #[test]
+    fn keeps_the_path_without_variants() {
+        let dir = icon_dir("none", &["copy.png"]);
+        let path = dir.join("copy.png");
+
+        assert_eq!(
+            scaled_variant(path.to_str().unwrap(), 2.0),
+            (path.clone(), 1)
+        );
+    }
+}
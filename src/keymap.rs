@@ -1,21 +1,13 @@
 use std::collections::HashMap;
 
+use crate::accelerator::Modifiers;
+
 #[cfg(target_os = "linux")]
 use gdk::keys::constants;
 
 #[cfg(target_os = "windows")]
 pub fn get_key_map() -> HashMap<&'static str, &'static str> {
     let mut key_map = HashMap::new();
-    key_map.insert("cmd", "Ctrl"); // Alias for "ctrl"
-    key_map.insert("cmd_or_ctrl", "Ctrl"); // Alias for "ctrl"
-    key_map.insert("shift", "Shift");
-    key_map.insert("alt", "Alt");
-    key_map.insert("ctrl", "Ctrl");
-    key_map.insert("opt", "Alt"); // Alias for "alt"
-    key_map.insert("altgr", "AltGr");
-    key_map.insert("super", "Super");
-    key_map.insert("win", "Win");
-    key_map.insert("meta", "Meta");
     key_map.insert("plus", "Plus");
     key_map.insert("space", "Space");
     key_map.insert("tab", "Tab");
@@ -26,7 +18,6 @@ pub fn get_key_map() -> HashMap<&'static str, &'static str> {
     key_map.insert("delete", "Delete");
     key_map.insert("insert", "Insert");
     key_map.insert("return", "Return");
-    key_map.insert("enter", "Return");
     key_map.insert("up", "UpArrow");
     key_map.insert("down", "DownArrow");
     key_map.insert("left", "LeftArrow");
@@ -36,7 +27,6 @@ pub fn get_key_map() -> HashMap<&'static str, &'static str> {
     key_map.insert("pageup", "PageUp");
     key_map.insert("pagedown", "PageDown");
     key_map.insert("escape", "Escape");
-    key_map.insert("esc", "Escape");
     key_map.insert("num0", "Numpad0");
     key_map.insert("num1", "Numpad1");
     key_map.insert("num2", "Numpad2");
@@ -80,6 +70,23 @@ pub fn get_key_map() -> HashMap<&'static str, &'static str> {
     key_map
 }
 
+#[cfg(target_os = "windows")]
+pub fn get_modifier_labels(modifiers: &Modifiers) -> Vec<&'static str> {
+    let labels = [
+        (modifiers.cmd_or_ctrl || modifiers.ctrl, "Ctrl"),
+        (modifiers.alt, "Alt"),
+        (modifiers.alt_gr, "AltGr"),
+        (modifiers.shift, "Shift"),
+        (modifiers.super_key, "Win"),
+        (modifiers.meta, "Meta"),
+    ];
+    labels
+        .iter()
+        .filter(|(active, _)| *active)
+        .map(|(_, label)| *label)
+        .collect()
+}
+
 #[cfg(target_os = "macos")]
 pub fn get_key_map() -> HashMap<&'static str, &'static str> {
     let mut key_map = HashMap::new();
@@ -93,7 +100,6 @@ pub fn get_key_map() -> HashMap<&'static str, &'static str> {
     key_map.insert("delete", "\u{2326}");
     key_map.insert("insert", "\u{2380}");
     key_map.insert("return", "\u{23ce}");
-    key_map.insert("enter", "\u{23ce}");
     key_map.insert("up", "\u{2191}");
     key_map.insert("down", "\u{2193}");
     key_map.insert("left", "\u{2190}");
@@ -103,7 +109,6 @@ pub fn get_key_map() -> HashMap<&'static str, &'static str> {
     key_map.insert("pageup", "\u{21DE}");
     key_map.insert("pagedown", "\u{21DF}");
     key_map.insert("escape", "\u{238b}");
-    key_map.insert("esc", "\u{238b}");
     key_map.insert("num0", "\u{30}");
     key_map.insert("num1", "\u{31}");
     key_map.insert("num2", "\u{32}");
@@ -148,55 +153,28 @@ pub fn get_key_map() -> HashMap<&'static str, &'static str> {
 }
 
 #[cfg(target_os = "macos")]
-pub fn get_modifier_map() -> HashMap<&'static str, cocoa::appkit::NSEventModifierFlags> {
-    let mut mod_map = HashMap::new();
-    mod_map.insert("cmd", cocoa::appkit::NSEventModifierFlags::NSCommandKeyMask);
-    mod_map.insert(
-        "cmd_or_ctrl",
-        cocoa::appkit::NSEventModifierFlags::NSCommandKeyMask,
-    ); // Alias for "cmd"
-    mod_map.insert("shift", cocoa::appkit::NSEventModifierFlags::NSShiftKeyMask);
-    mod_map.insert(
-        "alt",
-        cocoa::appkit::NSEventModifierFlags::NSAlternateKeyMask,
-    );
-    mod_map.insert(
-        "ctrl",
-        cocoa::appkit::NSEventModifierFlags::NSControlKeyMask,
-    );
-    mod_map.insert(
-        "opt",
-        cocoa::appkit::NSEventModifierFlags::NSAlternateKeyMask,
-    ); // Alias for "alt"
-    mod_map.insert(
-        "altgr",
-        cocoa::appkit::NSEventModifierFlags::NSAlternateKeyMask,
-    ); // Alias for "alt"
-    mod_map.insert(
-        "super",
-        cocoa::appkit::NSEventModifierFlags::NSCommandKeyMask,
-    ); // Alias for "cmd"
-    mod_map.insert("win", cocoa::appkit::NSEventModifierFlags::NSCommandKeyMask); // Alias for "cmd"
-    mod_map.insert(
-        "meta",
-        cocoa::appkit::NSEventModifierFlags::NSCommandKeyMask,
-    );
-    mod_map
+pub fn get_modifier_mask(modifiers: &Modifiers) -> cocoa::appkit::NSEventModifierFlags {
+    use cocoa::appkit::NSEventModifierFlags;
+
+    let mut mask = NSEventModifierFlags::empty();
+    if modifiers.cmd_or_ctrl || modifiers.super_key || modifiers.meta {
+        mask.insert(NSEventModifierFlags::NSCommandKeyMask);
+    }
+    if modifiers.ctrl {
+        mask.insert(NSEventModifierFlags::NSControlKeyMask);
+    }
+    if modifiers.shift {
+        mask.insert(NSEventModifierFlags::NSShiftKeyMask);
+    }
+    if modifiers.alt || modifiers.alt_gr {
+        mask.insert(NSEventModifierFlags::NSAlternateKeyMask);
+    }
+    mask
 }
 
 #[cfg(target_os = "linux")]
 pub fn get_key_map() -> HashMap<&'static str, gdk::keys::Key> {
     let mut key_map = HashMap::new();
-    key_map.insert("cmd", constants::Control_L); // Alias for "ctrl"
-    key_map.insert("cmd_or_ctrl", constants::Control_L); // Alias for "ctrl"
-    key_map.insert("shift", constants::Shift_L);
-    key_map.insert("alt", constants::Alt_L);
-    key_map.insert("ctrl", constants::Control_L);
-    key_map.insert("opt", constants::Alt_L); // Alias for "alt"
-    key_map.insert("altgr", constants::Alt_R);
-    key_map.insert("super", constants::Super_L);
-    key_map.insert("win", constants::Super_L);
-    key_map.insert("meta", constants::Super_L);
     key_map.insert("plus", constants::plus);
     key_map.insert("space", constants::space);
     key_map.insert("tab", constants::Tab);
@@ -207,7 +185,6 @@ pub fn get_key_map() -> HashMap<&'static str, gdk::keys::Key> {
     key_map.insert("delete", constants::Delete);
     key_map.insert("insert", constants::Insert);
     key_map.insert("return", constants::Return);
-    key_map.insert("enter", constants::Return);
     key_map.insert("up", constants::Up);
     key_map.insert("down", constants::Down);
     key_map.insert("left", constants::Left);
@@ -217,7 +194,6 @@ pub fn get_key_map() -> HashMap<&'static str, gdk::keys::Key> {
     key_map.insert("pageup", constants::Page_Up);
     key_map.insert("pagedown", constants::Page_Down);
     key_map.insert("escape", constants::Escape);
-    key_map.insert("esc", constants::Escape);
     key_map.insert("num0", constants::KP_0);
     key_map.insert("num1", constants::KP_1);
     key_map.insert("num2", constants::KP_2);
@@ -262,20 +238,27 @@ pub fn get_key_map() -> HashMap<&'static str, gdk::keys::Key> {
 }
 
 #[cfg(target_os = "linux")]
-pub fn get_mod_map() -> HashMap<&'static str, gdk::ModifierType> {
+pub fn get_modifier_mask(modifiers: &Modifiers) -> gdk::ModifierType {
     use gdk::ModifierType;
 
-    let mut mod_map = HashMap::new();
-    mod_map.insert("cmd", ModifierType::CONTROL_MASK); // Alias for "ctrl"
-    mod_map.insert("cmd_or_ctrl", ModifierType::CONTROL_MASK); // Alias for "ctrl"
-    mod_map.insert("shift", ModifierType::SHIFT_MASK);
-    mod_map.insert("alt", ModifierType::MOD1_MASK);
-    mod_map.insert("ctrl", ModifierType::CONTROL_MASK);
-    mod_map.insert("opt", ModifierType::MOD1_MASK); // Alias for "alt"
-    mod_map.insert("altgr", ModifierType::MOD5_MASK);
-    mod_map.insert("super", ModifierType::SUPER_MASK);
-    mod_map.insert("win", ModifierType::SUPER_MASK);
-    mod_map.insert("meta", ModifierType::META_MASK);
-
-    mod_map
+    let mut mask = ModifierType::empty();
+    if modifiers.cmd_or_ctrl || modifiers.ctrl {
+        mask.insert(ModifierType::CONTROL_MASK);
+    }
+    if modifiers.shift {
+        mask.insert(ModifierType::SHIFT_MASK);
+    }
+    if modifiers.alt {
+        mask.insert(ModifierType::MOD1_MASK);
+    }
+    if modifiers.alt_gr {
+        mask.insert(ModifierType::MOD5_MASK);
+    }
+    if modifiers.super_key {
+        mask.insert(ModifierType::SUPER_MASK);
+    }
+    if modifiers.meta {
+        mask.insert(ModifierType::META_MASK);
+    }
+    mask
 }
@@ -0,0 +1,63 @@
+use serde::Serialize;
+
+use crate::MenuItem;
+
+// Label of the placeholder shown in lazy submenus until their items are loaded
+pub const LOADING_LABEL: &str = "Loading…";
+pub const LOADING_LABEL_KEY: &str = "context_menu.loading";
+
+/// Function returning the items of a lazy submenu from Rust, given the `lazy` event name and
+/// the `payload` of its item. Returning `None` emits the event to JavaScript instead.
+pub type SubmenuProvider = dyn Fn(&str, Option<&str>) -> Option<Vec<MenuItem>> + Send + Sync;
+
+/// Payload of the event emitted when a lazy submenu is opened, the items are sent back
+/// with the `fill_submenu` command and the same `request`.
+#[derive(Clone, Serialize)]
+pub struct SubmenuRequest {
+    pub request: u32,
+    pub payload: Option<String>,
+}
+
+pub struct LazySubmenus {
+    provider: Option<Box<SubmenuProvider>>,
+}
+
+impl LazySubmenus {
+    pub fn new(provider: Option<Box<SubmenuProvider>>) -> Self {
+        Self { provider }
+    }
+
+    pub fn provide(&self, event: &str, payload: Option<&str>) -> Option<Vec<MenuItem>> {
+        self.provider
+            .as_ref()
+            .and_then(|provider| provider(event, payload))
+    }
+
+    /// Fills the lazy submenus of `items` and all their subitems from the provider,
+    /// for platforms that can't load them while the menu is open.
+    ///
+    /// Fails for lazy submenus the provider doesn't fill and without `subitems` to show instead,
+    /// as their event can't be emitted to JavaScript.
+    #[cfg(not(target_os = "linux"))]
+    pub fn fill_eagerly(&self, items: &mut [MenuItem]) -> Result<(), String> {
+        for item in items.iter_mut() {
+            if let Some(event) = &item.lazy {
+                match self.provide(event, item.payload.as_deref()) {
+                    Some(subitems) => item.subitems = Some(subitems),
+                    None if item.subitems.is_none() => {
+                        return Err(format!(
+                            "Lazy submenu `{}` can only be loaded from JavaScript on Linux, provide it from Rust or give the item subitems",
+                            event
+                        ));
+                    }
+                    None => {}
+                }
+            }
+            if let Some(subitems) = &mut item.subitems {
+                self.fill_eagerly(subitems)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,12 +1,41 @@
 use serde::Deserialize;
-use tauri::{plugin::Builder, plugin::TauriPlugin, Runtime, Window};
+use tauri::{
+    plugin::Builder as PluginBuilder, plugin::TauriPlugin, Manager, RunEvent, Runtime, State,
+    Window, WindowEvent,
+};
 
+mod accelerator;
+mod close;
+mod direction;
+mod handles;
+mod icon;
 mod keymap;
+mod lazy;
+mod locale;
+mod markup;
 mod menu_item;
+mod mnemonic;
+mod overflow;
+mod position;
+mod scope;
+mod shortcuts;
 mod theme;
+mod trigger;
+mod units;
 
-use menu_item::MenuItem;
+use accelerator::Accelerator;
+use direction::Direction;
+use lazy::{LazySubmenus, SubmenuProvider};
+use locale::{Localizer, DEFAULT_LOCALE};
+use overflow::OverflowConfig;
+use position::Gravity;
+use scope::{IconScope, DEFAULT_ICON_SCOPE};
 use theme::Theme;
+use trigger::Trigger;
+use units::Units;
+
+pub use menu_item::{MenuItem, MenuItemIcon, MenuItemUpdate};
+pub use overflow::Overflow;
 
 #[cfg(target_os = "windows")]
 mod win_image_handler;
@@ -22,6 +51,9 @@ mod macos_window_holder;
 #[path = "macos.rs"]
 mod os;
 
+#[cfg(target_os = "linux")]
+mod coordinates;
+
 #[cfg(target_os = "linux")]
 #[path = "linux.rs"]
 mod os;
@@ -31,20 +63,393 @@ pub struct Position {
     x: f64,
     y: f64,
     is_absolute: Option<bool>,
+    // Size of the anchor rectangle starting at `x` and `y`
+    width: Option<f64>,
+    height: Option<f64>,
+    rect_anchor: Option<Gravity>,
+    menu_anchor: Option<Gravity>,
+    offset_x: Option<f64>,
+    offset_y: Option<f64>,
+    flip: Option<bool>,
+    slide: Option<bool>,
+    units: Option<Units>,
+    // Set by the JS API, used for the zoom level when the webview doesn't report it
+    device_pixel_ratio: Option<f64>,
+}
+
+impl Position {
+    // Converts the coordinates to logical pixels of the window
+    fn into_logical<R: Runtime>(mut self, window: &Window<R>) -> Self {
+        let units = self.units.unwrap_or_default();
+        if units == Units::Logical {
+            return self;
+        }
+
+        let scale_factor = window.scale_factor().unwrap_or(1.0);
+        let zoom = match units {
+            Units::Css => units::zoom_level(
+                os::webview_zoom(window),
+                self.device_pixel_ratio,
+                scale_factor,
+            ),
+            _ => 1.0,
+        };
+        let factor = units.to_logical(scale_factor, zoom);
+
+        self.x *= factor;
+        self.y *= factor;
+        self.width = self.width.map(|width| width * factor);
+        self.height = self.height.map(|height| height * factor);
+        self.offset_x = self.offset_x.map(|offset| offset * factor);
+        self.offset_y = self.offset_y.map(|offset| offset * factor);
+        self.units = Some(Units::Logical);
+        self
+    }
+}
+
+// Splits, localizes, validates and resolves the icons of the items before they are shown
+pub(crate) fn prepare_items<R: Runtime>(
+    window: &Window<R>,
+    items: &mut Vec<MenuItem>,
+) -> Result<(), String> {
+    #[cfg(not(target_os = "linux"))]
+    window.state::<LazySubmenus>().fill_eagerly(items)?;
+    let overflow = window.state::<OverflowConfig>();
+    overflow.apply(items, overflow.max_items(window));
+    window.state::<Localizer>().localize(items);
+    accelerator::validate_shortcuts(items)?;
+    markup::validate_markup(items)?;
+    icon::resolve_icons(
+        items,
+        &window.app_handle().path_resolver(),
+        &window.state::<IconScope>(),
+        window.scale_factor().unwrap_or(1.0),
+    )
 }
 
+// Localizes the labels and resolves the icons of the updates like those of the items
+fn prepare_updates<R: Runtime>(
+    window: &Window<R>,
+    updates: &mut [MenuItemUpdate],
+) -> Result<(), String> {
+    let mut items: Vec<MenuItem> = updates.iter().map(MenuItemUpdate::to_item).collect();
+    window.state::<Localizer>().localize(&mut items);
+    icon::resolve_icons(
+        &mut items,
+        &window.app_handle().path_resolver(),
+        &window.state::<IconScope>(),
+        window.scale_factor().unwrap_or(1.0),
+    )?;
+    for (update, item) in updates.iter_mut().zip(items) {
+        update.label = item.label;
+        update.icon = item.icon;
+    }
+    Ok(())
+}
+
+// Each option of the menu is a separate argument of the command
+#[allow(clippy::too_many_arguments)]
 #[tauri::command]
 fn show_context_menu<R: Runtime>(
     window: Window<R>,
+    localizer: State<'_, Localizer>,
     pos: Option<Position>,
-    items: Option<Vec<MenuItem>>,
+    mut items: Option<Vec<MenuItem>>,
+    theme: Option<String>,
+    direction: Option<String>,
+    filter: Option<bool>,
+    trigger: Option<String>,
+    keep_open_on_toggle: Option<bool>,
+) -> Result<(), String> {
+    let theme = theme.and_then(|s| Theme::from_str(&s));
+    // Follow the locale of the labels unless a direction is given
+    let direction = direction
+        .and_then(|s| Direction::from_str(&s))
+        .unwrap_or_else(|| Direction::from_locale(&localizer.locale()));
+    if let Some(items) = &mut items {
+        if let Some(keep_open) = keep_open_on_toggle {
+            menu_item::keep_open_on_toggle(items, keep_open);
+        }
+        prepare_items(&window, items)?;
+    }
+    let pos = pos.map(|pos| pos.into_logical(&window));
+    os::show_context_menu(
+        window,
+        pos,
+        items,
+        theme,
+        direction,
+        filter.unwrap_or(false),
+        trigger
+            .and_then(|s| Trigger::from_str(&s))
+            .unwrap_or(Trigger::Pointer),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn create_menu<R: Runtime>(
+    window: Window<R>,
+    localizer: State<'_, Localizer>,
+    mut items: Vec<MenuItem>,
     theme: Option<String>,
-) {
+    direction: Option<String>,
+    filter: Option<bool>,
+    keep_open_on_toggle: Option<bool>,
+) -> Result<u32, String> {
     let theme = theme.and_then(|s| Theme::from_str(&s));
-    os::show_context_menu(window, pos, items, theme);
+    let direction = direction
+        .and_then(|s| Direction::from_str(&s))
+        .unwrap_or_else(|| Direction::from_locale(&localizer.locale()));
+    if let Some(keep_open) = keep_open_on_toggle {
+        menu_item::keep_open_on_toggle(&mut items, keep_open);
+    }
+    prepare_items(&window, &mut items)?;
+    let handle = handles::reserve(window.label());
+    os::create_menu(
+        &window,
+        handle,
+        items,
+        theme,
+        direction,
+        filter.unwrap_or(false),
+    );
+    Ok(handle)
+}
+
+#[tauri::command]
+fn show_menu<R: Runtime>(
+    window: Window<R>,
+    handle: u32,
+    pos: Option<Position>,
+    trigger: Option<String>,
+) -> Result<(), String> {
+    handles::check(window.label(), handle)?;
+    let pos = pos.map(|pos| pos.into_logical(&window));
+    os::show_menu(
+        window,
+        handle,
+        pos,
+        trigger
+            .and_then(|s| Trigger::from_str(&s))
+            .unwrap_or(Trigger::Pointer),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn destroy_menu<R: Runtime>(window: Window<R>, handle: u32) -> Result<(), String> {
+    handles::release(window.label(), handle)?;
+    os::destroy_menu(handle);
+    Ok(())
+}
+
+#[tauri::command]
+fn fill_submenu<R: Runtime>(
+    window: Window<R>,
+    request: u32,
+    mut items: Vec<MenuItem>,
+) -> Result<(), String> {
+    prepare_items(&window, &mut items)?;
+    os::fill_submenu(request, items);
+    Ok(())
+}
+
+#[tauri::command]
+fn hide_context_menu<R: Runtime>(window: Window<R>) {
+    os::hide_context_menu(&window);
+}
+
+#[tauri::command]
+fn update_context_menu<R: Runtime>(
+    window: Window<R>,
+    handle: Option<u32>,
+    mut updates: Vec<MenuItemUpdate>,
+) -> Result<(), String> {
+    if let Some(handle) = handle {
+        handles::check(window.label(), handle)?;
+    }
+    prepare_updates(&window, &mut updates)?;
+    os::update_context_menu(&window, handle, updates);
+    Ok(())
+}
+
+#[tauri::command]
+fn register_shortcuts<R: Runtime>(
+    window: Window<R>,
+    menu: String,
+    items: Vec<MenuItem>,
+) -> Result<Vec<String>, String> {
+    shortcuts::register(window.label(), &menu, &items)
+}
+
+#[tauri::command]
+fn unregister_shortcuts<R: Runtime>(window: Window<R>, menu: String) {
+    shortcuts::unregister(window.label(), &menu);
+}
+
+#[tauri::command]
+fn trigger_shortcut<R: Runtime>(window: Window<R>, shortcut: String) -> Result<bool, String> {
+    let accelerator = shortcut
+        .parse::<Accelerator>()
+        .map_err(|err| format!("Invalid shortcut `{}`: {}", shortcut, err))?;
+
+    match shortcuts::find(window.label(), accelerator) {
+        Some((event, payload)) => {
+            window
+                .emit(&event, payload)
+                .map_err(|err| err.to_string())?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+fn set_locale(localizer: State<'_, Localizer>, locale: String) {
+    localizer.set_locale(&locale);
+}
+
+/// Switches the locale used to resolve the `label_key` of menu items.
+pub fn set_menu_locale<R: Runtime, M: Manager<R>>(manager: &M, locale: &str) {
+    manager.state::<Localizer>().set_locale(locale);
+}
+
+/// Closes the menu shown in `window`, if any.
+///
+/// The `menu-did-close` event is emitted with the `programmatic` reason.
+pub fn hide_menu<R: Runtime>(window: &Window<R>) {
+    os::hide_context_menu(window);
+}
+
+/// Changes the items with the ids of the updates in the menu shown in `window`,
+/// without closing it.
+///
+/// Fails when an icon of the updates can't be loaded.
+pub fn update_menu<R: Runtime>(
+    window: &Window<R>,
+    mut updates: Vec<MenuItemUpdate>,
+) -> Result<(), String> {
+    prepare_updates(window, &mut updates)?;
+    os::update_context_menu(window, None, updates);
+    Ok(())
 }
+
+/// Builder for the context menu plugin.
+#[derive(Default)]
+pub struct Builder {
+    icon_scope: Option<Vec<String>>,
+    catalogs: Vec<(String, String)>,
+    locale: Option<String>,
+    submenu_provider: Option<Box<SubmenuProvider>>,
+    overflow: Overflow,
+    max_items: Option<usize>,
+}
+
+impl Builder {
+    /// Sets the glob patterns of the files that can be loaded as menu item icons.
+    ///
+    /// Patterns can start with `$RESOURCE`, `$APPCONFIG`, `$APPDATA`, `$APPLOCALDATA`,
+    /// `$APPCACHE` or `$APPLOG`. Defaults to `$RESOURCE/**`.
+    pub fn icon_scope<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.icon_scope = Some(patterns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Adds a JSON catalog mapping message keys to the labels of `locale`.
+    ///
+    /// Messages can contain `{name}` placeholders, replaced with the `label_args` of the item.
+    pub fn catalog(mut self, locale: impl Into<String>, json: impl Into<String>) -> Self {
+        self.catalogs.push((locale.into(), json.into()));
+        self
+    }
+
+    /// Sets the initial locale, also used for keys missing from the current locale.
+    /// Defaults to `en`.
+    pub fn locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Sets how menus with more items than fit on the screen are shown.
+    /// Defaults to [`Overflow::Submenu`], [`Overflow::Scroll`] is only supported on Windows.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Limits the number of items shown in a menu or submenu,
+    /// menus are also limited to the items fitting on the screen.
+    pub fn max_items(mut self, max_items: usize) -> Self {
+        self.max_items = Some(max_items);
+        self
+    }
+
+    /// Sets the function providing the items of lazy submenus from Rust.
+    ///
+    /// It receives the `lazy` event name and the `payload` of the item, and can return
+    /// `None` to let the event be emitted to JavaScript.
+    pub fn submenu_provider<F>(mut self, provider: F) -> Self
+    where
+        F: Fn(&str, Option<&str>) -> Option<Vec<MenuItem>> + Send + Sync + 'static,
+    {
+        self.submenu_provider = Some(Box::new(provider));
+        self
+    }
+
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
+        let patterns = self
+            .icon_scope
+            .unwrap_or_else(|| vec![DEFAULT_ICON_SCOPE.to_string()]);
+        let catalogs = self.catalogs;
+        let locale = self.locale.unwrap_or_else(|| DEFAULT_LOCALE.to_string());
+        let submenu_provider = self.submenu_provider;
+        let overflow = self.overflow;
+        let max_items = self.max_items;
+
+        PluginBuilder::new("context_menu")
+            .invoke_handler(tauri::generate_handler![
+                show_context_menu,
+                hide_context_menu,
+                update_context_menu,
+                create_menu,
+                show_menu,
+                destroy_menu,
+                fill_submenu,
+                register_shortcuts,
+                unregister_shortcuts,
+                trigger_shortcut,
+                set_locale
+            ])
+            .setup(move |app| {
+                app.manage(IconScope::new(&patterns, &app.path_resolver())?);
+                app.manage(Localizer::new(&catalogs, &locale)?);
+                app.manage(LazySubmenus::new(submenu_provider));
+                app.manage(OverflowConfig::new(overflow, max_items)?);
+                Ok(())
+            })
+            .on_event(|_app, event| {
+                // Shortcuts and created menus live as long as their window
+                if let RunEvent::WindowEvent {
+                    label,
+                    event: WindowEvent::Destroyed,
+                    ..
+                } = event
+                {
+                    shortcuts::unregister_window(label);
+                    for handle in handles::release_window(label) {
+                        os::destroy_menu(handle);
+                    }
+                }
+            })
+            .build()
+    }
+}
+
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
-    Builder::new("context_menu")
-        .invoke_handler(tauri::generate_handler![show_context_menu])
-        .build()
+    Builder::default().build()
 }
@@ -1,18 +1,117 @@
-use gdk::{keys::Key, Display, ModifierType};
+use gdk::{gdk_pixbuf::Pixbuf, keys::Key, prelude::GdkPixbufExt, ModifierType};
+use glib::signal::Inhibit;
 use gtk::{prelude::*, traits::WidgetExt, AccelFlags, AccelGroup, Menu};
-use std::{env, mem, thread::sleep, time};
-use tauri::{Runtime, Window};
-
-use crate::keymap::{get_key_map, get_mod_map};
+use serde::Serialize;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    path::Path,
+    rc::Rc,
+    sync::atomic::{AtomicU32, Ordering},
+    thread::sleep,
+    time,
+};
+use tauri::{Manager, Runtime, Window};
+
+use crate::accelerator::{Accelerator, Key as AcceleratorKey};
+use crate::close::{CloseReason, MenuClosed};
+use crate::coordinates::{
+    clamp_to_window, find_webview, map_to_window, work_area, Backend, CoordinateMapping,
+};
+use crate::direction::Direction;
+use crate::icon::variant_scale;
+use crate::keymap::{get_key_map, get_modifier_mask};
+use crate::lazy::{LazySubmenus, SubmenuRequest, LOADING_LABEL, LOADING_LABEL_KEY};
+use crate::locale::Localizer;
+use crate::markup::Markup;
+use crate::mnemonic::Mnemonic;
+use crate::position::{Gravity, Placement, Rect};
 use crate::theme::Theme;
-use crate::{MenuItem, Position};
+use crate::trigger::Trigger;
+use crate::{prepare_items, MenuItem, MenuItemIcon, MenuItemUpdate, Position};
+
+// Placeholder of the filter entry, localized with the `context_menu.filter` key
+const FILTER_PLACEHOLDER: &str = "Filter";
+const FILTER_PLACEHOLDER_KEY: &str = "context_menu.filter";
+
+// Style of the badges shown after item labels
+const BADGE_CSS: &str = "label {
+    background-color: alpha(@theme_fg_color, 0.15);
+    border-radius: 8px;
+    padding: 0 6px;
+    margin-left: 8px;
+    font-size: smaller;
+}";
+
+// Id of the next request for the items of a lazy submenu
+static NEXT_SUBMENU_REQUEST: AtomicU32 = AtomicU32::new(0);
+
+// Fills a lazy submenu with the items received for it
+type SubmenuFill = Box<dyn FnOnce(Vec<MenuItem>)>;
+
+// Request of a lazy submenu, with the shown menu it belongs to
+struct PendingSubmenu {
+    menu: Menu,
+    fill: SubmenuFill,
+}
+
+thread_local! {
+    // Lazy submenus waiting for their items from JavaScript, by request id
+    static PENDING_SUBMENUS: RefCell<HashMap<u32, PendingSubmenu>> = RefCell::new(HashMap::new());
+
+    // Set while the state of check items is updated, which activates them
+    static UPDATING_ITEMS: Cell<bool> = const { Cell::new(false) };
+
+    // Menus being shown, by window label
+    static OPEN_MENUS: RefCell<HashMap<String, OpenMenu>> = RefCell::new(HashMap::new());
+
+    // Menus created with `create_menu` and their direction, by handle
+    static CREATED_MENUS: RefCell<HashMap<u32, (Menu, Direction)>> = RefCell::new(HashMap::new());
+}
+
+// A menu being shown, with the reason it will be closed for
+struct OpenMenu {
+    menu: Menu,
+    reason: CloseReason,
+    trigger: Trigger,
+}
+
+// Payload of the `menu-did-open` event
+#[derive(Clone, Serialize)]
+struct MenuOpened {
+    mapping: CoordinateMapping,
+    // Unknown on Wayland, where the compositor places the menu
+    placement: Option<MenuPlacement>,
+}
+
+// Where the menu was placed, in the coordinates of the window
+#[derive(Clone, Serialize)]
+struct MenuPlacement {
+    menu: Rect,
+    work_area: Rect,
+}
 
 pub fn on_context_menu<R: Runtime>(
     pos: Option<Position>,
     items: Option<Vec<MenuItem>>,
     window: Window<R>,
+    direction: Direction,
+    filter: bool,
+    trigger: Trigger,
 ) {
     // Create and show the context menu
+    let menu = build_menu(&window, items, direction, filter);
+    popup_menu(window, menu, pos, direction, trigger);
+}
+
+// Creates a menu with a single accelerator group for the shortcuts of its items,
+// added to the window while the menu is shown
+fn build_menu<R: Runtime>(
+    window: &Window<R>,
+    items: Option<Vec<MenuItem>>,
+    direction: Direction,
+    filter: bool,
+) -> Menu {
     let gtk_window = window.gtk_window().unwrap();
 
     // Check if the window is realized
@@ -20,74 +119,90 @@ pub fn on_context_menu<R: Runtime>(
         gtk_window.realize();
     }
 
-    // Create a new menu.
     let menu = Menu::new();
+    let accel_group = AccelGroup::new();
+    menu.set_accel_group(Some(&accel_group));
     if let Some(menu_items) = items {
+        if filter {
+            let placeholder = window
+                .state::<Localizer>()
+                .text(FILTER_PLACEHOLDER_KEY, FILTER_PLACEHOLDER);
+            append_filter_entry(&menu, menu_items.clone(), &placeholder);
+        }
         for item in menu_items.iter() {
-            append_menu_item(&window, &gtk_window, &menu, item);
-        }
-    }
-
-    let (mut x, mut y) = match pos {
-        Some(ref position) => (position.x as i32, position.y as i32),
-        None => {
-            if let Some(display) = Display::default() {
-                if let Some(seat) = display.default_seat() {
-                    let pointer = seat.pointer();
-                    let (_screen, x, y) = match pointer {
-                        Some(p) => p.position(),
-                        None => {
-                            eprintln!("Failed to get pointer position");
-                            (display.default_screen(), 0, 0)
-                        }
-                    };
-                    (x, y)
-                } else {
-                    eprintln!("Failed to get default seat");
-                    (0, 0)
-                }
-            } else {
-                eprintln!("Failed to get default display");
-                (0, 0)
+            append_menu_item(window, &gtk_window, &accel_group, &menu, item);
+        }
+    }
+    if direction == Direction::Rtl {
+        set_direction(menu.upcast_ref(), gtk::TextDirection::Rtl);
+    }
+    let window_clone = window.clone();
+    menu.connect_deactivate(move |menu| {
+        if let Some(accel_group) = menu.accel_group() {
+            gtk_window.remove_accel_group(&accel_group);
+        }
+        // Drop the requests of this menu only, another one may be shown already
+        PENDING_SUBMENUS.with(|pending| {
+            pending
+                .borrow_mut()
+                .retain(|_, submenu| &submenu.menu != menu)
+        });
+
+        // Another menu may have been opened in the window already
+        let open_menu = OPEN_MENUS.with(|menus| {
+            let mut menus = menus.borrow_mut();
+            match menus.get(window_clone.label()) {
+                Some(open_menu) if &open_menu.menu == menu => menus.remove(window_clone.label()),
+                _ => None,
+            }
+        });
+        let reason = open_menu
+            .as_ref()
+            .map_or(CloseReason::User, |open_menu| open_menu.reason);
+        // GTK deactivates the menu before activating the chosen item, emit the event once the
+        // item is handled so that it comes last as on the other platforms
+        let window = window_clone.clone();
+        glib::idle_add_local_once(move || {
+            window
+                .emit("menu-did-close", MenuClosed::new(reason))
+                .unwrap();
+        });
+
+        // Give the focus back to the page for keyboard users
+        if open_menu.map_or(false, |open_menu| open_menu.trigger == Trigger::Keyboard) {
+            if let Some(webview) = find_webview(gtk_window.upcast_ref()) {
+                webview.grab_focus();
             }
         }
-    };
-
-    // Adjust for X11 backend
-    let is_x11 = env::var("GDK_BACKEND").map_or(false, |v| v == "x11");
-
-    if is_x11 {
-        // Get the display and the default seat
-        let display = gdk::Display::default().expect("Failed to get default display");
-        let gdk_window = gtk_window.window().unwrap();
-
-        // Identify the monitor where the window is displayed
-        let monitor = display
-            .monitor_at_window(&gdk_window)
-            .expect("Failed to get monitor at window");
-
-        // Get the scale factor for the monitor
-        let scale_factor = monitor.scale_factor();
+    });
 
-        // Get the geometry of the monitor
-        let monitor_geometry = monitor.geometry();
+    menu
+}
 
-        // Adjust `x` and `y` based on the monitor's geometry and scale factor
-        x = (x + monitor_geometry.x()) * scale_factor;
-        y = (y + monitor_geometry.y()) * scale_factor;
+fn popup_menu<R: Runtime>(
+    window: Window<R>,
+    menu: Menu,
+    pos: Option<Position>,
+    direction: Direction,
+    trigger: Trigger,
+) {
+    let gtk_window = window.gtk_window().unwrap();
+    if let Some(accel_group) = menu.accel_group() {
+        gtk_window.add_accel_group(&accel_group);
     }
 
-    let is_absolute = if let Some(position) = pos.clone() {
-        position.is_absolute
-    } else {
-        Some(false)
+    let placement = Placement::new(pos.as_ref(), direction);
+    let (width, height) = match pos {
+        Some(ref position) => (
+            position.width.unwrap_or(0.0) as i32,
+            position.height.unwrap_or(0.0) as i32,
+        ),
+        None => (0, 0),
     };
-    if is_absolute.unwrap_or(false) || pos.is_none() {
-        // Adjust x and y if the coordinates are not relative to the window
-        let window_position = window.outer_position().unwrap();
-        x -= window_position.x;
-        y -= window_position.y;
-    }
+
+    // Convert the position to the coordinates of the window, past its decorations
+    let mapping = map_to_window(&gtk_window, pos.as_ref());
+    let (x, y) = (mapping.output.x as i32, mapping.output.y as i32);
 
     // Required otherwise the menu doesn't show properly
     sleep(time::Duration::from_millis(100));
@@ -96,7 +211,6 @@ pub fn on_context_menu<R: Runtime>(
     glib::idle_add_local(move || {
         // Show the context menu at the specified position.
         let gdk_window = gtk_window.window().unwrap();
-        let rect = &gdk::Rectangle::new(x, y, 0, 0);
         let mut event = gdk::Event::new(gdk::EventType::ButtonPress);
         event.set_device(
             gdk_window
@@ -106,29 +220,390 @@ pub fn on_context_menu<R: Runtime>(
                 .as_ref(),
         );
         menu.show_all();
-        menu.popup_at_rect(
-            &gdk_window,
-            rect,
-            gdk::Gravity::NorthWest,
-            gdk::Gravity::NorthWest,
-            Some(&event),
-        );
+
+        // Wayland compositors keep popups on the screen themselves and don't tell where
+        // the window is, elsewhere the menu is kept in the work area of the monitor
+        let anchor = Rect::new(x as f64, y as f64, width as f64, height as f64);
+        let area = match mapping.backend {
+            Backend::Wayland => None,
+            _ => work_area(&gdk_window, mapping.window_origin, &anchor),
+        };
+
+        let placed = match area {
+            Some(area) => {
+                let (_, natural) = menu.preferred_size();
+                let (menu_width, menu_height) = (natural.width as f64, natural.height as f64);
+                let (menu_x, menu_y) = placement.place(&anchor, menu_width, menu_height, &area);
+
+                // Only let GTK shrink menus taller than the work area
+                menu.set_anchor_hints(gdk::AnchorHints::RESIZE);
+                menu.popup_at_rect(
+                    &gdk_window,
+                    &gdk::Rectangle::new(menu_x.round() as i32, menu_y.round() as i32, 1, 1),
+                    gdk::Gravity::NorthWest,
+                    gdk::Gravity::NorthWest,
+                    Some(&event),
+                );
+                Some(MenuPlacement {
+                    menu: Rect::new(menu_x, menu_y, menu_width, menu_height),
+                    work_area: area,
+                })
+            }
+            None => {
+                let mut rect = gdk::Rectangle::new(x, y, width, height);
+                // The menu is a popup of the window surface on Wayland, anchored inside of it
+                if mapping.backend == Backend::Wayland {
+                    rect = clamp_to_window(&rect, &gdk_window);
+                }
+                // Let GTK flip, slide and resize the menu to keep it on the screen as requested
+                let mut anchor_hints = gdk::AnchorHints::RESIZE;
+                if placement.flip {
+                    anchor_hints |= gdk::AnchorHints::FLIP;
+                }
+                if placement.slide {
+                    anchor_hints |= gdk::AnchorHints::SLIDE;
+                }
+                menu.set_anchor_hints(anchor_hints);
+                menu.set_rect_anchor_dx(placement.offset_x as i32);
+                menu.set_rect_anchor_dy(placement.offset_y as i32);
+
+                menu.popup_at_rect(
+                    &gdk_window,
+                    &rect,
+                    to_gdk_gravity(placement.rect_anchor),
+                    to_gdk_gravity(placement.menu_anchor),
+                    Some(&event),
+                );
+                None
+            }
+        };
+        if trigger == Trigger::Keyboard {
+            menu.select_first(true);
+        }
+        OPEN_MENUS.with(|menus| {
+            menus.borrow_mut().insert(
+                window.label().to_string(),
+                OpenMenu {
+                    menu: menu.clone(),
+                    reason: CloseReason::User,
+                    trigger,
+                },
+            )
+        });
+
+        window
+            .emit(
+                "menu-did-open",
+                MenuOpened {
+                    mapping: mapping.clone(),
+                    placement: placed,
+                },
+            )
+            .unwrap();
         Continue(false)
     });
 }
 
+// Updates the items of the menu created with `handle`, or of the menu shown in `window`,
+// in place from any thread
+pub fn update_context_menu<R: Runtime>(
+    window: &Window<R>,
+    handle: Option<u32>,
+    updates: Vec<MenuItemUpdate>,
+) {
+    let window = window.clone();
+    glib::MainContext::default().invoke(move || {
+        let menu = match handle {
+            Some(handle) => CREATED_MENUS
+                .with(|menus| menus.borrow().get(&handle).map(|(menu, _)| menu.clone())),
+            None => OPEN_MENUS.with(|menus| {
+                menus
+                    .borrow()
+                    .get(window.label())
+                    .map(|open_menu| open_menu.menu.clone())
+            }),
+        };
+        let (menu, gtk_window) = match (menu, window.gtk_window()) {
+            (Some(menu), Ok(gtk_window)) => (menu, gtk_window),
+            _ => return,
+        };
+        for update in updates.iter() {
+            match find_menu_item(&menu, &update.id) {
+                Some(menu_item) => update_menu_item(&gtk_window, &menu_item, update),
+                None => eprintln!("No menu item with id {}", update.id),
+            }
+        }
+    });
+}
+
+// Closes the menu shown in `window`, from any thread
+pub fn hide_context_menu<R: Runtime>(window: &Window<R>) {
+    let label = window.label().to_string();
+    glib::MainContext::default().invoke(move || {
+        let menu = OPEN_MENUS.with(|menus| {
+            menus.borrow_mut().get_mut(&label).map(|open_menu| {
+                open_menu.reason = CloseReason::Programmatic;
+                open_menu.menu.clone()
+            })
+        });
+        // Deactivating the menu pops it down and runs its deactivate handler
+        if let Some(menu) = menu {
+            menu.deactivate();
+        }
+    });
+}
+
+pub fn create_menu<R: Runtime>(
+    window: &Window<R>,
+    handle: u32,
+    items: Vec<MenuItem>,
+    _theme: Option<Theme>,
+    direction: Direction,
+    filter: bool,
+) {
+    let menu = build_menu(window, Some(items), direction, filter);
+    CREATED_MENUS.with(|menus| menus.borrow_mut().insert(handle, (menu, direction)));
+}
+
+pub fn show_menu<R: Runtime>(
+    window: Window<R>,
+    handle: u32,
+    pos: Option<Position>,
+    trigger: Trigger,
+) {
+    if let Some((menu, direction)) =
+        CREATED_MENUS.with(|menus| menus.borrow().get(&handle).cloned())
+    {
+        popup_menu(window, menu, pos, direction, trigger);
+    }
+}
+
+pub fn destroy_menu(handle: u32) {
+    let menu = match CREATED_MENUS.with(|menus| menus.borrow_mut().remove(&handle)) {
+        Some((menu, _)) => menu,
+        None => return,
+    };
+    // Close the menu first if it is shown
+    let is_open = OPEN_MENUS.with(|menus| {
+        match menus
+            .borrow_mut()
+            .values_mut()
+            .find(|open_menu| open_menu.menu == menu)
+        {
+            Some(open_menu) => {
+                open_menu.reason = CloseReason::Programmatic;
+                true
+            }
+            None => false,
+        }
+    });
+    if is_open {
+        menu.deactivate();
+    }
+    // Menus are kept alive by their toplevel window until they are destroyed
+    unsafe { menu.destroy() };
+}
+
+// The zoom level of the webview of `window`
+pub fn webview_zoom<R: Runtime>(window: &Window<R>) -> Option<f64> {
+    let gtk_window = window.gtk_window().ok()?;
+    find_webview(gtk_window.upcast_ref()).map(|webview| webview.property::<f64>("zoom-level"))
+}
+
 pub fn show_context_menu<R: Runtime>(
     window: Window<R>,
     pos: Option<Position>,
     items: Option<Vec<MenuItem>>,
     _theme: Option<Theme>,
+    direction: Direction,
+    filter: bool,
+    trigger: Trigger,
 ) {
-    on_context_menu(pos, items, window);
+    on_context_menu(pos, items, window, direction, filter, trigger);
+}
+
+// Adds an entry at the top of `menu` filtering its items by label as the user types.
+// The menu keeps the keyboard grab, so the typed keys are forwarded to the entry.
+// Access keys are activated with Alt, as typing a letter filters the items.
+fn append_filter_entry(menu: &Menu, items: Vec<MenuItem>, placeholder: &str) {
+    let entry = gtk::Entry::new();
+    entry.set_placeholder_text(Some(placeholder));
+    let entry_item = gtk::MenuItem::new();
+    entry_item.add(&entry);
+    // Clicking the entry must not close the menu
+    entry_item.connect_button_release_event(|_, _| Inhibit(true));
+    menu.append(&entry_item);
+    menu.append(&gtk::SeparatorMenuItem::new());
+
+    let items = Rc::new(items);
+    let menu_clone = menu.clone();
+    entry.connect_changed(move |entry| {
+        let query = entry.text().to_lowercase();
+        // Skip the entry and its separator
+        let children: Vec<gtk::Widget> = menu_clone.children().into_iter().skip(2).collect();
+        filter_menu_items(&children, &items, &query);
+    });
+
+    // Created menus are shown again, start from a clear filter showing all the items
+    let entry_clone = entry.clone();
+    menu.connect_deactivate(move |_| entry_clone.set_text(""));
+
+    let menu_clone = menu.clone();
+    menu.connect_key_press_event(move |_, event| {
+        let mut text = entry.text().to_string();
+        let keyval = event.keyval();
+        let with_modifier = event
+            .state()
+            .intersects(ModifierType::CONTROL_MASK | ModifierType::MOD1_MASK);
+
+        if event.state() & (ModifierType::CONTROL_MASK | ModifierType::MOD1_MASK)
+            == ModifierType::MOD1_MASK
+        {
+            let children: Vec<gtk::Widget> = menu_clone.children().into_iter().skip(2).collect();
+            return match mnemonic_item(&children, &keyval) {
+                Some(menu_item) if menu_item.submenu().is_some() => {
+                    menu_clone.select_item(&menu_item);
+                    Inhibit(true)
+                }
+                Some(menu_item) => {
+                    menu_clone.deactivate();
+                    menu_item.activate();
+                    Inhibit(true)
+                }
+                None => Inhibit(false),
+            };
+        } else if keyval == gdk::keys::constants::BackSpace {
+            text.pop();
+        } else if keyval == gdk::keys::constants::Return || keyval == gdk::keys::constants::KP_Enter
+        {
+            if text.is_empty() {
+                return Inhibit(false);
+            }
+            let children: Vec<gtk::Widget> = menu_clone.children().into_iter().skip(2).collect();
+            if let Some(first_match) = first_visible_item(&children) {
+                menu_clone.deactivate();
+                first_match.activate();
+            }
+            return Inhibit(true);
+        } else {
+            match keyval.to_unicode() {
+                Some(c) if !with_modifier && !c.is_control() => text.push(c),
+                // Navigation and other keys are handled by the menu
+                _ => return Inhibit(false),
+            }
+        }
+
+        entry.set_text(&text);
+        Inhibit(true)
+    });
+}
+
+// Shows the `widgets` of `items` whose label contains `query`, or with subitems matching it,
+// and returns whether any is shown. Separators are hidden while filtering.
+fn filter_menu_items(widgets: &[gtk::Widget], items: &[MenuItem], query: &str) -> bool {
+    let mut any_visible = false;
+    for (widget, item) in widgets.iter().zip(items.iter()) {
+        let visible = if item.is_separator.unwrap_or(false) {
+            query.is_empty()
+        } else {
+            let matches = item_text(item).to_lowercase().contains(query);
+            // Show the whole submenu of a matching item, only the matches otherwise
+            let submenu_matches = match (&item.subitems, widget.downcast_ref::<gtk::MenuItem>()) {
+                (Some(subitems), Some(menu_item)) => menu_item.submenu().map_or(false, |submenu| {
+                    let children = submenu
+                        .downcast_ref::<gtk::Container>()
+                        .map(|container| container.children())
+                        .unwrap_or_default();
+                    filter_menu_items(&children, subitems, if matches { "" } else { query })
+                }),
+                _ => false,
+            };
+            matches || submenu_matches
+        };
+
+        widget.set_visible(visible);
+        any_visible |= visible && !item.is_separator.unwrap_or(false);
+    }
+    any_visible
+}
+
+// Returns the first shown and enabled item without a submenu, depth first.
+fn first_visible_item(widgets: &[gtk::Widget]) -> Option<gtk::MenuItem> {
+    widgets
+        .iter()
+        .filter(|widget| widget.is_visible() && widget.is_sensitive())
+        .filter_map(|widget| widget.downcast_ref::<gtk::MenuItem>())
+        .filter(|menu_item| !menu_item.is::<gtk::SeparatorMenuItem>())
+        .find_map(|menu_item| match menu_item.submenu() {
+            Some(submenu) => submenu
+                .downcast_ref::<gtk::Container>()
+                .and_then(|container| first_visible_item(&container.children())),
+            None => Some(menu_item.clone()),
+        })
+}
+
+// The shown item of `widgets` with `keyval` as access key
+fn mnemonic_item(widgets: &[gtk::Widget], keyval: &Key) -> Option<gtk::MenuItem> {
+    widgets
+        .iter()
+        .filter(|widget| widget.is_visible() && widget.is_sensitive())
+        .filter_map(|widget| widget.downcast_ref::<gtk::MenuItem>())
+        .find(|menu_item| {
+            menu_item
+                .child()
+                .and_then(|child| child.downcast::<gtk::Box>().ok())
+                .and_then(|content| item_label(&content))
+                .map_or(false, |label| {
+                    Key::from(label.mnemonic_keyval()).to_lower() == keyval.to_lower()
+                })
+        })
+        .cloned()
+}
+
+// The label of `item` as displayed, without formatting or access key markers
+fn item_text(item: &MenuItem) -> String {
+    match item.markup.as_deref().map(str::parse::<Markup>) {
+        Some(Ok(markup)) => markup.text().to_string(),
+        _ => Mnemonic::parse(item.label.as_deref().unwrap_or(""))
+            .text()
+            .to_string(),
+    }
+}
+
+fn to_gdk_gravity(gravity: Gravity) -> gdk::Gravity {
+    match gravity {
+        Gravity::NorthWest => gdk::Gravity::NorthWest,
+        Gravity::North => gdk::Gravity::North,
+        Gravity::NorthEast => gdk::Gravity::NorthEast,
+        Gravity::West => gdk::Gravity::West,
+        Gravity::Center => gdk::Gravity::Center,
+        Gravity::East => gdk::Gravity::East,
+        Gravity::SouthWest => gdk::Gravity::SouthWest,
+        Gravity::South => gdk::Gravity::South,
+        Gravity::SouthEast => gdk::Gravity::SouthEast,
+    }
+}
+
+// Sets the direction of `widget`, its children and their submenus,
+// as GTK doesn't propagate it to widgets that are already created
+fn set_direction(widget: &gtk::Widget, direction: gtk::TextDirection) {
+    widget.set_direction(direction);
+    if let Some(menu_item) = widget.downcast_ref::<gtk::MenuItem>() {
+        if let Some(submenu) = menu_item.submenu() {
+            set_direction(&submenu, direction);
+        }
+    }
+    if let Some(container) = widget.downcast_ref::<gtk::Container>() {
+        for child in container.children() {
+            set_direction(&child, direction);
+        }
+    }
 }
 
 fn append_menu_item<R: Runtime>(
     window: &Window<R>,
     gtk_window: &gtk::ApplicationWindow,
+    accel_group: &AccelGroup,
     menu: &Menu,
     item: &MenuItem,
 ) {
@@ -140,6 +615,11 @@ fn append_menu_item<R: Runtime>(
                 // Create a CheckMenuItem for checkable items
                 let check_menu_item = gtk::CheckMenuItem::new();
                 check_menu_item.set_active(state);
+                // Toggle the item on click instead of letting the menu activate it and close,
+                // changing the state activates the item and emits its event
+                if item.keep_open_on_toggle.unwrap_or(false) {
+                    connect_keep_open(&check_menu_item);
+                }
                 check_menu_item.upcast()
             }
             None => {
@@ -153,31 +633,78 @@ fn append_menu_item<R: Runtime>(
         hbox.set_homogeneous(false);
 
         // Handle icon
-        if let Some(icon) = &item.icon {
-            let image = gtk::Image::from_file(&icon.path);
-            if let Some(width) = icon.width {
-                if let Some(height) = icon.height {
-                    image.set_pixel_size(width as i32);
-                    image.set_pixel_size(height as i32);
-                }
-            }
+        if let Some(image) = item
+            .icon
+            .as_ref()
+            .and_then(|icon| icon_image(gtk_window, icon))
+        {
             hbox.pack_start(&image, false, false, 0);
         }
 
-        // Add label to the Box
-        let label = item.label.as_deref().unwrap_or("");
-        let accel_label = gtk::AccelLabel::new(label);
-        accel_label.set_xalign(0.0); // Align the label to the left
-        hbox.pack_start(&accel_label, true, true, 0);
+        // Add label to the Box, either from the markup or with the access key marked for GTK
+        let text_label = gtk::Label::new(None);
+        match item.markup.as_deref().map(str::parse::<Markup>) {
+            Some(Ok(markup)) => text_label.set_markup(markup.to_pango()),
+            _ => {
+                let label = Mnemonic::parse(item.label.as_deref().unwrap_or(""));
+                text_label.set_text_with_mnemonic(&label.to_gtk());
+            }
+        }
+        text_label.set_xalign(0.0); // Align the label to the left
+
+        // Stack the description in smaller, dimmed text under the label
+        if let Some(description) = &item.description {
+            let vbox = gtk::Box::new(gtk::Orientation::Vertical, 0);
+            vbox.pack_start(&text_label, false, false, 0);
+
+            let description_label = gtk::Label::new(None);
+            description_label.set_markup(&format!(
+                "<small>{}</small>",
+                glib::markup_escape_text(description)
+            ));
+            description_label.set_xalign(0.0);
+            description_label.style_context().add_class("dim-label");
+            vbox.pack_start(&description_label, false, false, 0);
+
+            hbox.pack_start(&vbox, true, true, 0);
+        } else {
+            hbox.pack_start(&text_label, true, true, 0);
+        }
+
+        // Show the badge right-aligned, before the shortcut column
+        if let Some(badge) = &item.badge {
+            let badge_label = gtk::Label::new(Some(badge));
+            let provider = gtk::CssProvider::new();
+            if provider.load_from_data(BADGE_CSS.as_bytes()).is_ok() {
+                badge_label
+                    .style_context()
+                    .add_provider(&provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+            }
+            hbox.pack_start(&badge_label, false, false, 0);
+        }
+
+        // The accelerator label only shows the shortcut, on the right of the item
+        let accel_label = gtk::AccelLabel::new("");
+        hbox.pack_start(&accel_label, false, false, 0);
 
         // Add the Box to the MenuItem
         menu_item.add(&hbox);
 
+        // Items with an id can be found again to update them
+        if let Some(id) = &item.id {
+            menu_item.set_widget_name(id);
+        }
+
         // Handle enabled/disabled state
         if item.disabled.unwrap_or(false) {
             menu_item.set_sensitive(false);
         }
 
+        // Tooltips are shown on disabled items as well
+        if let Some(tooltip) = &item.tooltip {
+            menu_item.set_tooltip_text(Some(tooltip));
+        }
+
         // If an event is provided, you can connect to the "activate" signal (from item.event and item.payload)
         if let Some(event) = &item.event {
             let window_clone = window.clone();
@@ -188,6 +715,10 @@ fn append_menu_item<R: Runtime>(
             // get event from String to str
             let event_clone = event.clone();
             menu_item.connect_activate(move |_| {
+                // Check items are activated when their state is updated as well
+                if UPDATING_ITEMS.with(Cell::get) {
+                    return;
+                }
                 window_clone
                     .emit(event_clone.as_str(), &payload_clone)
                     .unwrap(); // Emit the event to JavaScript
@@ -195,20 +726,20 @@ fn append_menu_item<R: Runtime>(
         }
 
         // Handle shortcut
-        if let Some(shortcut) = &item.shortcut {
-            let accel_group = AccelGroup::new();
-            gtk_window.add_accel_group(&accel_group);
-
-            // Parse and assign the shortcut
-            let (key, mods) = parse_shortcut(shortcut);
+        if let Some((key, mods)) = item.shortcut.as_deref().and_then(parse_shortcut) {
             accel_label.set_accel_widget(Some(&menu_item));
-            menu_item.add_accelerator("activate", &accel_group, key, mods, AccelFlags::VISIBLE);
+            menu_item.add_accelerator("activate", accel_group, key, mods, AccelFlags::VISIBLE);
         }
 
-        if let Some(subitems) = &item.subitems {
+        if let Some(event) = &item.lazy {
+            let submenu = Menu::new();
+            submenu.append(&loading_menu_item(window));
+            menu_item.set_submenu(Some(&submenu));
+            connect_lazy_submenu(window, gtk_window, accel_group, &submenu, event, item);
+        } else if let Some(subitems) = &item.subitems {
             let submenu = Menu::new();
             for subitem in subitems.iter() {
-                append_menu_item(window, gtk_window, &submenu, subitem);
+                append_menu_item(window, gtk_window, accel_group, &submenu, subitem);
             }
             menu_item.set_submenu(Some(&submenu));
         }
@@ -217,37 +748,246 @@ fn append_menu_item<R: Runtime>(
     }
 }
 
-fn key_to_u32(key: gdk::keys::Key) -> u32 {
-    unsafe { mem::transmute(key) }
+// Renders the icon as a surface with the window's device scale
+// so it stays sharp on HiDPI screens
+fn icon_image(gtk_window: &gtk::ApplicationWindow, icon: &MenuItemIcon) -> Option<gtk::Image> {
+    let (pixbuf, scale) = if icon.width.is_none() && icon.height.is_none() {
+        // Keep the natural size, an `@Nx` variant is drawn at 1/N of its pixels
+        let scale = variant_scale(Path::new(&icon.path)) as i32;
+        (Pixbuf::from_file(&icon.path), scale)
+    } else {
+        // A missing dimension follows the aspect ratio of the image
+        let scale = gtk_window.scale_factor().max(1);
+        let size = |length: Option<u32>| length.map_or(-1, |length| length as i32 * scale);
+        let pixbuf =
+            Pixbuf::from_file_at_scale(&icon.path, size(icon.width), size(icon.height), true);
+        (pixbuf, scale)
+    };
+    match pixbuf {
+        Ok(pixbuf) => {
+            let surface = pixbuf.create_surface(scale, gtk_window.window().as_ref());
+            Some(gtk::Image::from_surface(surface.as_ref()))
+        }
+        Err(err) => {
+            eprintln!(
+                "Failed to load image from path: {}. Error: {:?}",
+                icon.path, err
+            );
+            None
+        }
+    }
+}
+
+// Finds the item with the widget name `id` in `menu` and its submenus
+fn find_menu_item(menu: &Menu, id: &str) -> Option<gtk::MenuItem> {
+    menu.children().into_iter().find_map(|child| {
+        let item = child.downcast::<gtk::MenuItem>().ok()?;
+        if item.widget_name() == id {
+            return Some(item);
+        }
+        item.submenu()
+            .and_then(|submenu| submenu.downcast::<Menu>().ok())
+            .and_then(|submenu| find_menu_item(&submenu, id))
+    })
 }
 
-fn parse_shortcut(shortcut: &str) -> (u32, ModifierType) {
-    let key_map = get_key_map();
-    let mod_map = get_mod_map(); // This should map strings like "ctrl" to ModifierType
-    let parts: Vec<&str> = shortcut.split('+').collect();
+// The label of an item, first in its box or in the box stacking it with the description
+fn item_label(content: &gtk::Box) -> Option<gtk::Label> {
+    content
+        .children()
+        .into_iter()
+        .find_map(|child| match child.downcast::<gtk::Label>() {
+            Ok(label) => Some(label),
+            Err(child) => child
+                .downcast::<gtk::Box>()
+                .ok()?
+                .children()
+                .into_iter()
+                .next()?
+                .downcast::<gtk::Label>()
+                .ok(),
+        })
+}
 
-    // Assuming last part is always the key
-    let key_str = parts.last().unwrap_or(&"");
+fn update_menu_item(
+    gtk_window: &gtk::ApplicationWindow,
+    menu_item: &gtk::MenuItem,
+    update: &MenuItemUpdate,
+) {
+    let content = menu_item
+        .child()
+        .and_then(|child| child.downcast::<gtk::Box>().ok());
 
-    // Get the key from the key map
-    let key = if let Some(key) = key_map.get(key_str) {
-        // Clone the key value to get ownership of it
-        key.clone()
-    } else {
-        // If the key is not in the map, assume it's a character
-        Key::from_name(key_str)
-    };
+    if let Some(label) = &update.label {
+        if let Some(text_label) = content.as_ref().and_then(item_label) {
+            text_label.set_text_with_mnemonic(&Mnemonic::parse(label).to_gtk());
+        }
+    }
+    if let Some(disabled) = update.disabled {
+        menu_item.set_sensitive(!disabled);
+    }
+    if let (Some(checked), Some(check_menu_item)) = (
+        update.checked,
+        menu_item.downcast_ref::<gtk::CheckMenuItem>(),
+    ) {
+        UPDATING_ITEMS.with(|updating| updating.set(true));
+        check_menu_item.set_active(checked);
+        UPDATING_ITEMS.with(|updating| updating.set(false));
+    }
+    if let (Some(icon), Some(content)) = (&update.icon, &content) {
+        // Replace the icon, always first in the box
+        for child in content.children() {
+            if child.is::<gtk::Image>() {
+                content.remove(&child);
+            }
+        }
+        if let Some(image) = icon_image(gtk_window, icon) {
+            content.pack_start(&image, false, false, 0);
+            content.reorder_child(&image, 0);
+            image.show();
+        }
+    }
+}
 
-    let key_u32 = key_to_u32(key);
+fn loading_menu_item<R: Runtime>(window: &Window<R>) -> gtk::MenuItem {
+    let label = window
+        .state::<Localizer>()
+        .text(LOADING_LABEL_KEY, LOADING_LABEL);
+    let loading = gtk::MenuItem::with_label(&label);
+    loading.set_sensitive(false);
+    loading
+}
+
+// Toggles `check_menu_item` when clicked with the primary button without closing the menu.
+// Only a release following a press on the item toggles it, other releases (like the one
+// ending the click that opened the menu) are left to the menu and its guards.
+fn connect_keep_open(check_menu_item: &gtk::CheckMenuItem) {
+    let pressed = Rc::new(Cell::new(false));
 
-    let mut mods = ModifierType::empty();
+    let pressed_clone = pressed.clone();
+    check_menu_item.connect_button_press_event(move |_, event| {
+        pressed_clone.set(event.button() == 1);
+        Inhibit(false)
+    });
 
-    // Process all parts except the last one as modifiers
-    for &mod_str in &parts[..parts.len() - 1] {
-        if let Some(&mod_type) = mod_map.get(mod_str) {
-            mods.insert(mod_type);
+    let pressed_clone = pressed.clone();
+    check_menu_item.connect_button_release_event(move |check_menu_item, event| {
+        if event.button() != 1 || !pressed_clone.replace(false) {
+            return Inhibit(false);
         }
+        check_menu_item.set_active(!check_menu_item.is_active());
+        Inhibit(true)
+    });
+
+    // Created menus are shown again, forget a press released outside the item
+    check_menu_item.connect_unmap(move |_| pressed.set(false));
+}
+
+// Loads the items of `submenu` the first time it is shown, from the Rust provider
+// or by emitting the `event` of the lazy item and waiting for `fill_submenu`
+fn connect_lazy_submenu<R: Runtime>(
+    window: &Window<R>,
+    gtk_window: &gtk::ApplicationWindow,
+    accel_group: &AccelGroup,
+    submenu: &Menu,
+    event: &str,
+    item: &MenuItem,
+) {
+    // Whether the items are loaded, and the request waiting for them from JavaScript
+    let loaded = Rc::new(Cell::new(false));
+    let request = Cell::new(None);
+    let window = window.clone();
+    let gtk_window = gtk_window.clone();
+    let accel_group = accel_group.clone();
+    let event = event.to_string();
+    let payload = item.payload.clone();
+
+    submenu.connect_show(move |submenu| {
+        // Requests are dropped when the menu is closed, the submenu asks again once shown
+        let pending = request.get().map_or(false, |request| {
+            PENDING_SUBMENUS.with(|pending| pending.borrow().contains_key(&request))
+        });
+        if loaded.get() || pending {
+            return;
+        }
+
+        let fill = {
+            let loaded = loaded.clone();
+            let window = window.clone();
+            let gtk_window = gtk_window.clone();
+            let accel_group = accel_group.clone();
+            let submenu = submenu.clone();
+            move |items: Vec<MenuItem>| {
+                for child in submenu.children() {
+                    submenu.remove(&child);
+                }
+                for item in items.iter() {
+                    append_menu_item(&window, &gtk_window, &accel_group, &submenu, item);
+                }
+                set_direction(submenu.upcast_ref(), submenu.direction());
+                submenu.show_all();
+                loaded.set(true);
+            }
+        };
+
+        let provided = window
+            .state::<LazySubmenus>()
+            .provide(&event, payload.as_deref());
+        match provided {
+            Some(mut items) => match prepare_items(&window, &mut items) {
+                Ok(()) => fill(items),
+                Err(err) => eprintln!("Failed to load the submenu {}: {}", event, err),
+            },
+            None => {
+                let id = NEXT_SUBMENU_REQUEST.fetch_add(1, Ordering::Relaxed);
+                let pending_submenu = PendingSubmenu {
+                    menu: root_menu(submenu),
+                    fill: Box::new(fill),
+                };
+                PENDING_SUBMENUS.with(|pending| pending.borrow_mut().insert(id, pending_submenu));
+                request.set(Some(id));
+                let submenu_request = SubmenuRequest {
+                    request: id,
+                    payload: payload.clone(),
+                };
+                window.emit(&event, submenu_request).unwrap(); // Emit the event to JavaScript
+            }
+        }
+    });
+}
+
+pub fn fill_submenu(request: u32, items: Vec<MenuItem>) {
+    // Widgets can only be used from the GTK main thread
+    glib::MainContext::default().invoke(move || {
+        let pending_submenu =
+            PENDING_SUBMENUS.with(|pending| pending.borrow_mut().remove(&request));
+        if let Some(pending_submenu) = pending_submenu {
+            (pending_submenu.fill)(items);
+        }
+    });
+}
+
+// The shown menu that `submenu` is part of
+fn root_menu(submenu: &Menu) -> Menu {
+    let mut menu = submenu.clone();
+    while let Some(parent) = menu
+        .attach_widget()
+        .filter(|widget| widget.is::<gtk::MenuItem>())
+        .and_then(|item| item.parent())
+        .and_then(|parent| parent.downcast::<Menu>().ok())
+    {
+        menu = parent;
     }
+    menu
+}
+
+fn parse_shortcut(shortcut: &str) -> Option<(u32, ModifierType)> {
+    let accelerator: Accelerator = shortcut.parse().ok()?;
+
+    let key = match accelerator.key {
+        AcceleratorKey::Char(c) => Key::from_unicode(c),
+        AcceleratorKey::Named(name) => get_key_map().get(name)?.clone(),
+    };
 
-    (key_u32, mods)
+    Some((*key, get_modifier_mask(&accelerator.modifiers)))
 }
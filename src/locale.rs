@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use crate::MenuItem;
+
+// Locale used when the plugin is initialized without an explicit one
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Message catalogs used to resolve the `label_key` of menu items, by locale.
+pub struct Localizer {
+    catalogs: HashMap<String, HashMap<String, String>>,
+    default_locale: String,
+    locale: Mutex<String>,
+}
+
+impl Localizer {
+    /// Parses the JSON `catalogs`, given as `(locale, source)` pairs.
+    ///
+    /// Each catalog is an object mapping message keys to messages.
+    pub fn new(catalogs: &[(String, String)], locale: &str) -> Result<Self, String> {
+        let mut parsed: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for (catalog_locale, source) in catalogs.iter() {
+            let messages: HashMap<String, String> = serde_json::from_str(source)
+                .map_err(|err| format!("Invalid catalog for locale {}: {}", catalog_locale, err))?;
+            // Catalogs given more than once for the same locale are merged
+            parsed
+                .entry(normalize(catalog_locale))
+                .or_default()
+                .extend(messages);
+        }
+
+        Ok(Self {
+            catalogs: parsed,
+            default_locale: normalize(locale),
+            locale: Mutex::new(normalize(locale)),
+        })
+    }
+
+    pub fn locale(&self) -> String {
+        self.locale.lock().unwrap().clone()
+    }
+
+    pub fn set_locale(&self, locale: &str) {
+        *self.locale.lock().unwrap() = normalize(locale);
+    }
+
+    /// Looks `key` up in the current locale (`fr-ca`), its language (`fr`),
+    /// then the default locale.
+    fn message(&self, key: &str) -> Option<&str> {
+        let locale = self.locale();
+        let language = locale.split('-').next().unwrap_or("");
+
+        [locale.as_str(), language, self.default_locale.as_str()]
+            .iter()
+            .filter_map(|locale| self.catalogs.get(*locale))
+            .find_map(|catalog| catalog.get(key))
+            .map(String::as_str)
+    }
+
+    /// The message of `key` in the current locale, or `fallback` if no catalog has it.
+    pub fn text(&self, key: &str, fallback: &str) -> String {
+        self.message(key).unwrap_or(fallback).to_string()
+    }
+
+    /// Replaces the label of `items` and all their subitems with a `label_key` with the
+    /// message in the current locale. Items keep their `label` when the key is missing
+    /// from every catalog, or show the key if they have none.
+    pub fn localize(&self, items: &mut [MenuItem]) {
+        for item in items.iter_mut() {
+            if let Some(key) = &item.label_key {
+                match self.message(key) {
+                    Some(message) => {
+                        item.label = Some(interpolate(message, item.label_args.as_ref()));
+                    }
+                    None if item.label.is_none() => item.label = Some(key.clone()),
+                    None => {}
+                }
+            }
+            if let Some(subitems) = &mut item.subitems {
+                self.localize(subitems);
+            }
+        }
+    }
+}
+
+// Locales are compared case insensitively, with `_` and `-` as the same separator
+fn normalize(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_lowercase()
+}
+
+/// Replaces `{name}` placeholders in `message` with the matching argument.
+/// Placeholders without an argument are left as is, `{{` and `}}` are literal braces.
+fn interpolate(message: &str, args: Option<&HashMap<String, Value>>) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut rest = message;
+
+    while let Some(start) = rest.find(&['{', '}'][..]) {
+        result.push_str(&rest[..start]);
+        let brace = &rest[start..start + 1];
+        rest = &rest[start + 1..];
+
+        if rest.starts_with(brace) {
+            result.push_str(brace);
+            rest = &rest[1..];
+            continue;
+        }
+
+        let placeholder = if brace == "{" {
+            rest.find('}').map(|end| (&rest[..end], end))
+        } else {
+            None
+        };
+        match placeholder.and_then(|(name, end)| {
+            args.and_then(|args| args.get(name.trim()))
+                .map(|value| (value, end))
+        }) {
+            Some((value, end)) => {
+                match value {
+                    Value::String(value) => result.push_str(value),
+                    value => result.push_str(&value.to_string()),
+                }
+                rest = &rest[end + 1..];
+            }
+            None => result.push_str(brace),
+        }
+    }
+    result.push_str(rest);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn localizer() -> Localizer {
+        Localizer::new(
+            &[
+                (
+                    "en".to_string(),
+                    r#"{"open": "Open {name}", "quit": "Quit"}"#.to_string(),
+                ),
+                ("fr".to_string(), r#"{"open": "Ouvrir {name}"}"#.to_string()),
+            ],
+            "en",
+        )
+        .unwrap()
+    }
+
+    fn item(key: &str) -> MenuItem {
+        let mut args = HashMap::new();
+        args.insert("name".to_string(), Value::from("notes.txt"));
+        MenuItem {
+            label_key: Some(key.to_string()),
+            label_args: Some(args),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn looks_up_interface_texts() {
+        let localizer = localizer();
+        assert_eq!(localizer.text("quit", "Exit"), "Quit");
+        assert_eq!(localizer.text("context_menu.filter", "Filter"), "Filter");
+    }
+
+    #[test]
+    fn falls_back_to_language_then_default_locale() {
+        let localizer = localizer();
+        localizer.set_locale("fr_CA");
+        let mut items = vec![item("open"), item("quit"), item("missing")];
+        localizer.localize(&mut items);
+
+        assert_eq!(items[0].label.as_deref(), Some("Ouvrir notes.txt"));
+        assert_eq!(items[1].label.as_deref(), Some("Quit"));
+        assert_eq!(items[2].label.as_deref(), Some("missing"));
+    }
+
+    #[test]
+    fn interpolates_arguments() {
+        let mut args = HashMap::new();
+        args.insert("count".to_string(), Value::from(3));
+        assert_eq!(
+            interpolate("{count} files, {{literal}}, {unknown}", Some(&args)),
+            "3 files, {literal}, {unknown}"
+        );
+    }
+}
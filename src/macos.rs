@@ -1,16 +1,51 @@
 use cocoa::appkit::{NSControl, NSMenuItem};
-use cocoa::base::{id, nil, selector};
+use cocoa::base::{id, nil, selector, BOOL};
 use cocoa::foundation::{NSPoint, NSRect, NSSize, NSString};
 use objc::declare::ClassDecl;
 use objc::runtime::{Object, Sel, NO, YES};
 use objc::{class, msg_send, sel, sel_impl};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use tauri::{Runtime, Window};
 
-use crate::keymap::{get_key_map, get_modifier_map};
+use crate::accelerator::{Accelerator, Key};
+use crate::close::{CloseReason, MenuClosed};
+use crate::direction::Direction;
+use crate::keymap::{get_key_map, get_modifier_mask};
 use crate::macos_window_holder::CURRENT_WINDOW;
+use crate::markup::Markup;
+use crate::mnemonic::Mnemonic;
+use crate::position::{Placement, Rect};
 use crate::theme::Theme;
-use crate::{MenuItem, Position};
+use crate::trigger::Trigger;
+use crate::{MenuItem, MenuItemIcon, MenuItemUpdate, Position};
+
+lazy_static::lazy_static! {
+    // Items of the menu being shown, by id
+    static ref ITEMS: Mutex<HashMap<String, usize>> = Mutex::new(HashMap::new());
+    // The menu being shown
+    static ref OPEN_MENU: Mutex<Option<OpenMenu>> = Mutex::new(None);
+    // Menus created with `create_menu` by handle, with their items by id and direction
+    static ref CREATED_MENUS: Mutex<HashMap<u32, (usize, HashMap<String, usize>, Direction)>> = Mutex::new(HashMap::new());
+}
+
+// A menu being shown, with the reason it will be closed for
+struct OpenMenu {
+    menu: usize,
+    reason: CloseReason,
+    // Whether the menu is released once closed, it can't be while it tracks the mouse
+    release: bool,
+}
+
+// Tag of the check items toggled without closing the menu
+const KEEP_OPEN_TAG: isize = 1;
+
+// NSEventTypeKeyDown
+const NS_KEY_DOWN: usize = 10;
+
+// Set when an item kept open is toggled, to show the menu again once it closes
+static REOPEN: AtomicBool = AtomicBool::new(false);
 
 extern "C" {
     fn NSPointInRect(aPoint: NSPoint, aRect: NSRect) -> bool;
@@ -35,6 +70,15 @@ extern "C" fn menu_item_action<R: Runtime>(_self: &Object, _cmd: Sel, _item: id)
     let event_name = parts.get(0).unwrap_or(&"").to_string();
     let payload = parts.get(1).cloned();
 
+    // Toggle check items kept open, the menu is shown again once closed
+    let tag: isize = unsafe { msg_send![_item, tag] };
+    if tag == KEEP_OPEN_TAG {
+        let state: isize = unsafe { msg_send![_item, state] };
+        let state: isize = if state == 0 { 1 } else { 0 };
+        let _: () = unsafe { msg_send![_item, setState: state] };
+        REOPEN.store(true, Ordering::SeqCst);
+    }
+
     // Dereferencing the Arc to get a reference to the Window<R>
     let window = &*window_arc;
 
@@ -42,14 +86,6 @@ extern "C" fn menu_item_action<R: Runtime>(_self: &Object, _cmd: Sel, _item: id)
     window.emit(&event_name, payload).unwrap();
 }
 
-extern "C" fn menu_did_close<R: Runtime>(_self: &Object, _cmd: Sel, _menu: id) {
-    if let Some(window) = CURRENT_WINDOW.get_window::<R>() {
-        window.emit("menu-did-close", ()).unwrap();
-    } else {
-        println!("Menu did close, but no window was found.");
-    }
-}
-
 fn register_menu_item_action<R: Runtime>() -> Sel {
     let selector_name = "menuAction:";
 
@@ -66,10 +102,6 @@ fn register_menu_item_action<R: Runtime>() -> Sel {
                 selector(selector_name),
                 menu_item_action::<R> as extern "C" fn(&Object, Sel, id),
             );
-            decl.add_method(
-                selector("menuDidClose:"),
-                menu_did_close::<R> as extern "C" fn(&Object, Sel, id),
-            );
             decl.register();
         }
     }
@@ -77,7 +109,10 @@ fn register_menu_item_action<R: Runtime>() -> Sel {
     selector(selector_name)
 }
 
-fn create_custom_menu_item<R: Runtime>(option: &MenuItem) -> id {
+fn create_custom_menu_item<R: Runtime>(
+    option: &MenuItem,
+    items: &mut HashMap<String, usize>,
+) -> id {
     // If the item is a separator, return a separator item
     if option.is_separator.unwrap_or(false) {
         let separator: id = unsafe { msg_send![class!(NSMenuItem), separatorItem] };
@@ -86,34 +121,30 @@ fn create_custom_menu_item<R: Runtime>(option: &MenuItem) -> id {
 
     let sel = register_menu_item_action::<R>();
     let menu_item: id = unsafe {
-        let title = match &option.label {
-            Some(label) => NSString::alloc(nil).init_str(label),
-            None => NSString::alloc(nil).init_str(""),
+        // Markup labels fall back to their plain text and MacOS menus have no access keys
+        let label = match option.markup.as_deref().map(str::parse::<Markup>) {
+            Some(Ok(markup)) => markup.text().to_string(),
+            _ => Mnemonic::parse(option.label.as_deref().unwrap_or(""))
+                .text()
+                .to_string(),
         };
+        let title = NSString::alloc(nil).init_str(&label);
 
         // Parse the shortcut
-        let (key, mask) = match &option.shortcut {
-            Some(shortcut) => {
-                let parts: Vec<&str> = shortcut.split('+').collect();
-
-                let key_map = get_key_map();
-                let modifier_map = get_modifier_map();
-
-                // Default values
-                let mut key_str = "";
-                let mut mask = cocoa::appkit::NSEventModifierFlags::empty();
-
-                for part in parts.iter() {
-                    if let Some(k) = key_map.get(*part) {
-                        key_str = k;
-                    } else if let Some(m) = modifier_map.get(*part) {
-                        mask.insert(*m);
-                    } else {
-                        key_str = *part; // Assuming the last item or the only item without a '+' is the main key.
-                    }
-                }
-
-                (NSString::alloc(nil).init_str(key_str), mask)
+        let (key, mask) = match option
+            .shortcut
+            .as_deref()
+            .and_then(|shortcut| shortcut.parse::<Accelerator>().ok())
+        {
+            Some(accelerator) => {
+                let key_str = match accelerator.key {
+                    Key::Char(c) => c.to_string(),
+                    Key::Named(name) => get_key_map().get(name).unwrap_or(&"").to_string(),
+                };
+                (
+                    NSString::alloc(nil).init_str(&key_str),
+                    get_modifier_mask(&accelerator.modifiers),
+                )
             }
             None => (
                 NSString::alloc(nil).init_str(""),
@@ -131,6 +162,30 @@ fn create_custom_menu_item<R: Runtime>(option: &MenuItem) -> id {
             _ => YES,
         });
 
+        // Subtitles are only available since MacOS 14
+        if let Some(description) = &option.description {
+            let responds: bool = msg_send![item, respondsToSelector: sel!(setSubtitle:)];
+            if responds {
+                let ns_description = NSString::alloc(nil).init_str(description);
+                let _: () = msg_send![item, setSubtitle: ns_description];
+            }
+        }
+
+        // Badges are only available since MacOS 14
+        if let (Some(badge), Some(badge_class)) =
+            (&option.badge, objc::runtime::Class::get("NSMenuItemBadge"))
+        {
+            let ns_badge = NSString::alloc(nil).init_str(badge);
+            let menu_item_badge: id = msg_send![badge_class, alloc];
+            let menu_item_badge: id = msg_send![menu_item_badge, initWithString: ns_badge];
+            let _: () = msg_send![item, setBadge: menu_item_badge];
+        }
+
+        if let Some(tooltip) = &option.tooltip {
+            let ns_tooltip = NSString::alloc(nil).init_str(tooltip);
+            let _: () = msg_send![item, setToolTip: ns_tooltip];
+        }
+
         // Set the represented object as the event name and payload
         let string_payload = match &option.payload {
             Some(payload) => format!(
@@ -144,20 +199,13 @@ fn create_custom_menu_item<R: Runtime>(option: &MenuItem) -> id {
         let _: () = msg_send![item, setRepresentedObject:ns_string_payload];
 
         // Set the icon if it exists
-        if let Some(icon) = &option.icon {
-            let ns_string_path: id = NSString::alloc(nil).init_str(&icon.path);
-            let image: *mut Object = msg_send![class!(NSImage), alloc];
-            let image: *mut Object = msg_send![image, initWithContentsOfFile:ns_string_path];
-            if image.is_null() {
-                println!("Failed to load image from path: {}", icon.path);
-            } else {
-                let width = icon.width.unwrap_or(16);
-                let height = icon.height.unwrap_or(16);
-                let size = NSSize::new(width as f64, height as f64);
-                let _: () = msg_send![image, setSize:size];
+        if let Some(image) = option.icon.as_ref().and_then(|icon| icon_image(icon)) {
+            let _: () = msg_send![item, setImage:image];
+        }
 
-                let _: () = msg_send![item, setImage:image];
-            }
+        // Items with an id can be found again to update them
+        if let Some(item_id) = &option.id {
+            items.insert(item_id.clone(), item as usize);
         }
 
         // Set the delegate
@@ -172,7 +220,7 @@ fn create_custom_menu_item<R: Runtime>(option: &MenuItem) -> id {
             let submenu: id = msg_send![class!(NSMenu), new];
             let _: () = msg_send![submenu, setAutoenablesItems:NO];
             for subitem in subitems.iter() {
-                let sub_menu_item: id = create_custom_menu_item::<R>(subitem);
+                let sub_menu_item: id = create_custom_menu_item::<R>(subitem, items);
                 let _: () = msg_send![submenu, addItem:sub_menu_item];
             }
             let _: () = msg_send![item, setSubmenu:submenu];
@@ -184,6 +232,12 @@ fn create_custom_menu_item<R: Runtime>(option: &MenuItem) -> id {
             _ => 0,
         };
         let _: () = msg_send![item, setState:state];
+        if option.checked.is_some()
+            && option.keep_open_on_toggle.unwrap_or(false)
+            && option.subitems.is_none()
+        {
+            let _: () = msg_send![item, setTag: KEEP_OPEN_TAG];
+        }
 
         item
     };
@@ -191,10 +245,45 @@ fn create_custom_menu_item<R: Runtime>(option: &MenuItem) -> id {
     menu_item
 }
 
+unsafe fn icon_image(icon: &MenuItemIcon) -> Option<id> {
+    let ns_string_path: id = NSString::alloc(nil).init_str(&icon.path);
+    let image: *mut Object = msg_send![class!(NSImage), alloc];
+    let image: *mut Object = msg_send![image, initWithContentsOfFile:ns_string_path];
+    if image.is_null() {
+        println!("Failed to load image from path: {}", icon.path);
+        return None;
+    }
+
+    let width = icon.width.unwrap_or(16);
+    let height = icon.height.unwrap_or(16);
+    let size = NSSize::new(width as f64, height as f64);
+    let _: () = msg_send![image, setSize:size];
+    Some(image)
+}
+
+unsafe fn update_menu_item(item: id, update: &MenuItemUpdate) {
+    if let Some(label) = &update.label {
+        let title = NSString::alloc(nil).init_str(Mnemonic::parse(label).text());
+        let _: () = msg_send![item, setTitle: title];
+    }
+    if let Some(disabled) = update.disabled {
+        item.setEnabled_(if disabled { NO } else { YES });
+    }
+    if let Some(checked) = update.checked {
+        let state = if checked { 1 } else { 0 };
+        let _: () = msg_send![item, setState: state];
+    }
+    if let Some(image) = update.icon.as_ref().and_then(|icon| icon_image(icon)) {
+        let _: () = msg_send![item, setImage: image];
+    }
+}
+
 fn create_context_menu<R: Runtime>(
     options: &[MenuItem],
     window: &Window<R>,
     theme: Option<Theme>,
+    direction: Direction,
+    items: &mut HashMap<String, usize>,
 ) -> id {
     let _: () = CURRENT_WINDOW.set_window(window.clone());
     unsafe {
@@ -213,92 +302,314 @@ fn create_context_menu<R: Runtime>(
             let _: () = msg_send![menu, setAppearance: appearance];
         }
 
+        // NSUserInterfaceLayoutDirectionRightToLeft
+        if direction == Direction::Rtl {
+            let _: () = msg_send![menu, setUserInterfaceLayoutDirection: 1_isize];
+        }
+
         let _: () = msg_send![menu, setAutoenablesItems:NO];
 
         for option in options.iter().cloned() {
-            let item: id = create_custom_menu_item::<R>(&option);
+            let item: id = create_custom_menu_item::<R>(&option, items);
             let _: () = msg_send![menu, addItem:item];
         }
 
-        let delegate_class_name = "MenuItemDelegate";
-        let delegate_class: &'static objc::runtime::Class =
-            objc::runtime::Class::get(delegate_class_name).expect("Class should exist");
-        let delegate_instance: id = msg_send![delegate_class, new];
-        let _: () = msg_send![menu, setDelegate:delegate_instance];
-
         menu
     }
 }
 
+// Updates the items of the menu created with `handle`, or of the menu being shown,
+// open menus reflect the changes right away
+pub fn update_context_menu<R: Runtime>(
+    _window: &Window<R>,
+    handle: Option<u32>,
+    updates: Vec<MenuItemUpdate>,
+) {
+    dispatch::Queue::main().exec_async(move || {
+        let items = match handle {
+            Some(handle) => match CREATED_MENUS.lock().unwrap().get(&handle) {
+                Some((_, items, _)) => items.clone(),
+                None => return,
+            },
+            None => ITEMS.lock().unwrap().clone(),
+        };
+        for update in updates.iter() {
+            match items.get(&update.id) {
+                Some(item) => unsafe { update_menu_item(*item as id, update) },
+                None => println!("No menu item with id {}", update.id),
+            }
+        }
+    });
+}
+
+// Closes the menu being shown, menus track the mouse on the main thread
+pub fn hide_context_menu<R: Runtime>(_window: &Window<R>) {
+    let menu = match OPEN_MENU.lock().unwrap().as_mut() {
+        Some(open_menu) => {
+            open_menu.reason = CloseReason::Programmatic;
+            open_menu.menu
+        }
+        None => return,
+    };
+    dispatch::Queue::main().exec_async(move || unsafe {
+        let _: () = msg_send![menu as id, cancelTracking];
+    });
+}
+
+// Lazy submenus are filled before the menu is shown on MacOS
+pub fn fill_submenu(_request: u32, _items: Vec<MenuItem>) {}
+
+// The page zoom of the WKWebView of `window`
+pub fn webview_zoom<R: Runtime>(window: &Window<R>) -> Option<f64> {
+    let ns_window = window.ns_window().ok()? as id;
+    unsafe {
+        let webview: id = msg_send![ns_window, contentView];
+        let responds: bool = msg_send![webview, respondsToSelector: sel!(pageZoom)];
+        if webview == nil || !responds {
+            return None;
+        }
+        let zoom: f64 = msg_send![webview, pageZoom];
+        Some(zoom)
+    }
+}
+
 pub fn show_context_menu<R: Runtime>(
     window: Window<R>,
     pos: Option<Position>,
     items: Option<Vec<MenuItem>>,
     theme: Option<Theme>,
+    direction: Direction,
+    _filter: bool,
+    trigger: Trigger,
 ) {
     let main_queue = dispatch::Queue::main();
     main_queue.exec_async(move || {
+        let mut menu_items = HashMap::new();
         let items_slice = items.as_ref().map(|v| v.as_slice()).unwrap_or(&[]);
-        let menu = create_context_menu(items_slice, &window, theme);
-        let location = match pos {
-            // Convert web page coordinates to screen coordinates
-            Some(pos) if pos.x != 0.0 || pos.y != 0.0 => unsafe {
-                let window_position = window.outer_position().unwrap();
-
-                // Get all screens and the mouse location
-                let screens: id = msg_send![class!(NSScreen), screens];
-                let screen_count: usize = msg_send![screens, count];
-                let mouse_location: NSPoint = msg_send![class!(NSEvent), mouseLocation];
-
-                // Find the screen under the mouse cursor
-                let mut target_screen: id = nil;
-                let mut target_screen_frame: NSRect =
-                    NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(0.0, 0.0));
-
-                for i in 0..screen_count {
-                    let screen: id = msg_send![screens, objectAtIndex:i];
-                    let frame: NSRect = msg_send![screen, frame];
-                    if NSPointInRect(mouse_location, frame) {
-                        target_screen = screen;
-                        target_screen_frame = frame;
-                        break;
-                    }
+        let menu = create_context_menu(items_slice, &window, theme, direction, &mut menu_items);
+        *ITEMS.lock().unwrap() = menu_items;
+        popup_menu(&window, menu, pos, direction, trigger);
+    });
+}
+
+pub fn create_menu<R: Runtime>(
+    window: &Window<R>,
+    handle: u32,
+    items: Vec<MenuItem>,
+    theme: Option<Theme>,
+    direction: Direction,
+    _filter: bool,
+) {
+    let window = window.clone();
+    dispatch::Queue::main().exec_async(move || {
+        let mut menu_items = HashMap::new();
+        let menu = create_context_menu(&items, &window, theme, direction, &mut menu_items);
+        CREATED_MENUS
+            .lock()
+            .unwrap()
+            .insert(handle, (menu as usize, menu_items, direction));
+    });
+}
+
+pub fn show_menu<R: Runtime>(
+    window: Window<R>,
+    handle: u32,
+    pos: Option<Position>,
+    trigger: Trigger,
+) {
+    dispatch::Queue::main().exec_async(move || {
+        let (menu, menu_items, direction) = match CREATED_MENUS.lock().unwrap().get(&handle) {
+            Some(created) => created.clone(),
+            None => return,
+        };
+        // Events of the menu are emitted on the window showing it
+        let _: () = CURRENT_WINDOW.set_window(window.clone());
+        *ITEMS.lock().unwrap() = menu_items;
+        popup_menu(&window, menu as id, pos, direction, trigger);
+    });
+}
+
+pub fn destroy_menu(handle: u32) {
+    dispatch::Queue::main().exec_async(move || {
+        let menu = match CREATED_MENUS.lock().unwrap().remove(&handle) {
+            Some((menu, _, _)) => menu,
+            None => return,
+        };
+        // A menu being shown is released by `popup_menu` once it returns
+        if let Some(open_menu) = OPEN_MENU.lock().unwrap().as_mut() {
+            if open_menu.menu == menu {
+                open_menu.reason = CloseReason::Programmatic;
+                open_menu.release = true;
+                unsafe {
+                    let _: () = msg_send![menu as id, cancelTracking];
                 }
+                return;
+            }
+        }
+        unsafe {
+            let _: () = msg_send![menu as id, release];
+        }
+    });
+}
 
-                // Fallback to the main screen if no specific screen found
-                if target_screen == nil {
-                    target_screen = msg_send![class!(NSScreen), mainScreen];
-                    target_screen_frame = msg_send![target_screen, frame];
+// Pops up `menu` at `pos`, or at the mouse location, on the main thread
+fn popup_menu<R: Runtime>(
+    window: &Window<R>,
+    menu: id,
+    pos: Option<Position>,
+    direction: Direction,
+    trigger: Trigger,
+) {
+    let placement = Placement::new(pos.as_ref(), direction);
+    let location = match pos {
+        // Convert web page coordinates to screen coordinates
+        Some(pos) if pos.x != 0.0 || pos.y != 0.0 => unsafe {
+            let window_position = window.outer_position().unwrap();
+
+            // Get all screens and the mouse location
+            let screens: id = msg_send![class!(NSScreen), screens];
+            let screen_count: usize = msg_send![screens, count];
+            let mouse_location: NSPoint = msg_send![class!(NSEvent), mouseLocation];
+
+            // Find the screen under the mouse cursor
+            let mut target_screen: id = nil;
+            let mut target_screen_frame: NSRect =
+                NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(0.0, 0.0));
+
+            for i in 0..screen_count {
+                let screen: id = msg_send![screens, objectAtIndex:i];
+                let frame: NSRect = msg_send![screen, frame];
+                if NSPointInRect(mouse_location, frame) {
+                    target_screen = screen;
+                    target_screen_frame = frame;
+                    break;
                 }
+            }
 
-                let screen_height = target_screen_frame.size.height;
-                let screen_origin_y = target_screen_frame.origin.y;
-                let scale_factor = match window.scale_factor() {
-                    Ok(factor) => factor,
-                    Err(_) => 1.0, // Default to 1.0 if scale factor can't be retrieved
-                };
+            // Fallback to the main screen if no specific screen found
+            if target_screen == nil {
+                target_screen = msg_send![class!(NSScreen), mainScreen];
+                target_screen_frame = msg_send![target_screen, frame];
+            }
 
-                if pos.is_absolute.unwrap_or(false) {
-                    let x = pos.x;
-                    let y = screen_origin_y + screen_height - pos.y;
-                    NSPoint::new(x, y)
-                } else {
-                    let x = pos.x + (window_position.x as f64 / scale_factor);
-                    let y = screen_origin_y + screen_height
-                        - (window_position.y as f64 / scale_factor)
-                        - pos.y;
-                    NSPoint::new(x, y)
-                }
-            },
-            // Get the current mouse location if the web page didn't specify a position
-            _ => unsafe {
-                let event: NSPoint = msg_send![class!(NSEvent), mouseLocation];
-                NSPoint::new(event.x, event.y)
-            },
-        };
+            let screen_height = target_screen_frame.size.height;
+            let screen_origin_y = target_screen_frame.origin.y;
+            let scale_factor = match window.scale_factor() {
+                Ok(factor) => factor,
+                Err(_) => 1.0, // Default to 1.0 if scale factor can't be retrieved
+            };
+
+            // Anchor rectangle with the y axis pointing down from the top of the screen
+            let anchor = if pos.is_absolute.unwrap_or(false) {
+                Rect::new(
+                    pos.x,
+                    pos.y,
+                    pos.width.unwrap_or(0.0),
+                    pos.height.unwrap_or(0.0),
+                )
+            } else {
+                Rect::new(
+                    pos.x + (window_position.x as f64 / scale_factor),
+                    pos.y + (window_position.y as f64 / scale_factor),
+                    pos.width.unwrap_or(0.0),
+                    pos.height.unwrap_or(0.0),
+                )
+            };
+            let bounds = Rect::new(
+                target_screen_frame.origin.x,
+                0.0,
+                target_screen_frame.size.width,
+                screen_height,
+            );
+            let menu_size: NSSize = msg_send![menu, size];
+            let (x, y) = placement.place(&anchor, menu_size.width, menu_size.height, &bounds);
+
+            // The menu is placed by its top left corner, with the y axis pointing up
+            NSPoint::new(x, screen_origin_y + screen_height - y)
+        },
+        // Get the current mouse location if the web page didn't specify a position
+        _ => unsafe {
+            let event: NSPoint = msg_send![class!(NSEvent), mouseLocation];
+            NSPoint::new(event.x, event.y)
+        },
+    };
+    *OPEN_MENU.lock().unwrap() = Some(OpenMenu {
+        menu: menu as usize,
+        reason: CloseReason::User,
+        release: false,
+    });
+    // Select the first item like menus opened from the keyboard do,
+    // the key press is handled once the menu tracks events
+    if trigger == Trigger::Keyboard {
+        unsafe { post_down_arrow() };
+    }
+    loop {
+        REOPEN.store(false, Ordering::SeqCst);
+        // Tracks the menu until it is closed, the action of the chosen item is sent before returning
         unsafe {
             let _: () =
                 msg_send![menu, popUpMenuPositioningItem:nil atLocation:location inView:nil];
         }
-    });
+        // Show the menu again where it was after toggling an item kept open, unless it was hidden
+        let hidden = matches!(
+            *OPEN_MENU.lock().unwrap(),
+            Some(OpenMenu {
+                reason: CloseReason::Programmatic,
+                ..
+            })
+        );
+        if hidden || !REOPEN.load(Ordering::SeqCst) {
+            break;
+        }
+    }
+
+    // The items can't be updated once the menu is closed, it may be released
+    ITEMS.lock().unwrap().clear();
+
+    let (reason, release) = OPEN_MENU
+        .lock()
+        .unwrap()
+        .take()
+        .map_or((CloseReason::User, false), |open_menu| {
+            (open_menu.reason, open_menu.release)
+        });
+    if release {
+        unsafe {
+            let _: () = msg_send![menu, release];
+        }
+    }
+
+    // Give the focus back to the page for keyboard users
+    if trigger == Trigger::Keyboard {
+        if let Ok(ns_window) = window.ns_window() {
+            unsafe {
+                let ns_window = ns_window as id;
+                let webview: id = msg_send![ns_window, contentView];
+                let _: BOOL = msg_send![ns_window, makeFirstResponder: webview];
+            }
+        }
+    }
+    window
+        .emit("menu-did-close", MenuClosed::new(reason))
+        .unwrap();
+}
+
+// Posts a press of the down arrow key to the application, handled by the next menu shown
+unsafe fn post_down_arrow() {
+    // NSDownArrowFunctionKey, with the virtual key code of the down arrow
+    let characters = NSString::alloc(nil).init_str("\u{F701}");
+    let event: id = msg_send![class!(NSEvent),
+        keyEventWithType: NS_KEY_DOWN
+        location: NSPoint::new(0.0, 0.0)
+        modifierFlags: 0usize
+        timestamp: 0.0f64
+        windowNumber: 0isize
+        context: nil
+        characters: characters
+        charactersIgnoringModifiers: characters
+        isARepeat: NO
+        keyCode: 125u16];
+    let app: id = msg_send![class!(NSApplication), sharedApplication];
+    let _: () = msg_send![app, postEvent: event atStart: NO];
+    let _: () = msg_send![characters, release];
 }
@@ -0,0 +1,296 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::MenuItem;
+
+/// Rich text label restricted to a safe subset of Pango markup:
+/// `<b>`, `<i>`, `<tt>` (or `<code>`) and `<span color="#rrggbb">`,
+/// plus the `&lt;`, `&gt;`, `&amp;`, `&quot;` and `&apos;` entities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Markup {
+    pango: String,
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupError {
+    UnknownTag(String),
+    UnknownAttribute(String),
+    InvalidColor(String),
+    UnexpectedClosingTag(String),
+    UnclosedTag(String),
+    UnterminatedTag,
+    InvalidEntity(String),
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkupError::UnknownTag(tag) => write!(f, "unsupported tag `<{}>`", tag),
+            MarkupError::UnknownAttribute(attribute) => {
+                write!(f, "unsupported attribute `{}`", attribute)
+            }
+            MarkupError::InvalidColor(color) => {
+                write!(f, "invalid color `{}`, expected #rgb or #rrggbb", color)
+            }
+            MarkupError::UnexpectedClosingTag(tag) => write!(f, "unexpected `</{}>`", tag),
+            MarkupError::UnclosedTag(tag) => write!(f, "`<{}>` is never closed", tag),
+            MarkupError::UnterminatedTag => write!(f, "tag without a closing `>`"),
+            MarkupError::InvalidEntity(entity) => write!(f, "unknown entity `&{};`", entity),
+        }
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+impl Markup {
+    /// The markup converted to Pango markup.
+    pub fn to_pango(&self) -> &str {
+        &self.pango
+    }
+
+    /// The text without any formatting.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl FromStr for Markup {
+    type Err = MarkupError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pango = String::with_capacity(s.len());
+        let mut text = String::with_capacity(s.len());
+        let mut open_tags: Vec<&str> = Vec::new();
+        let mut rest = s;
+
+        while let Some(c) = rest.chars().next() {
+            match c {
+                '<' => {
+                    let end = rest.find('>').ok_or(MarkupError::UnterminatedTag)?;
+                    let tag = &rest[1..end];
+                    rest = &rest[end + 1..];
+
+                    if let Some(name) = tag.strip_prefix('/') {
+                        let name = name.trim();
+                        match open_tags.pop() {
+                            Some(open) if open == name => {
+                                pango.push_str(&format!("</{}>", pango_tag(name)));
+                            }
+                            _ => return Err(MarkupError::UnexpectedClosingTag(name.to_string())),
+                        }
+                    } else {
+                        let (name, attributes) = match tag.find(char::is_whitespace) {
+                            Some(i) => (&tag[..i], tag[i..].trim()),
+                            None => (tag, ""),
+                        };
+                        pango.push_str(&open_tag(name, attributes)?);
+                        open_tags.push(name);
+                    }
+                }
+                '&' => {
+                    let end = rest
+                        .find(';')
+                        .ok_or_else(|| MarkupError::InvalidEntity(rest[1..].to_string()))?;
+                    let entity = &rest[1..end];
+                    let decoded = match entity {
+                        "lt" => '<',
+                        "gt" => '>',
+                        "amp" => '&',
+                        "quot" => '"',
+                        "apos" => '\'',
+                        _ => return Err(MarkupError::InvalidEntity(entity.to_string())),
+                    };
+                    pango.push_str(&rest[..=end]);
+                    text.push(decoded);
+                    rest = &rest[end + 1..];
+                }
+                _ => {
+                    match c {
+                        '>' => pango.push_str("&gt;"),
+                        _ => pango.push(c),
+                    }
+                    text.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+
+        match open_tags.pop() {
+            Some(tag) => Err(MarkupError::UnclosedTag(tag.to_string())),
+            None => Ok(Markup { pango, text }),
+        }
+    }
+}
+
+fn pango_tag(name: &str) -> &str {
+    match name {
+        "code" => "tt",
+        _ => name,
+    }
+}
+
+fn open_tag(name: &str, attributes: &str) -> Result<String, MarkupError> {
+    match name {
+        "b" | "i" | "tt" | "code" => {
+            if attributes.is_empty() {
+                Ok(format!("<{}>", pango_tag(name)))
+            } else {
+                Err(MarkupError::UnknownAttribute(attributes.to_string()))
+            }
+        }
+        "span" => {
+            // A single quoted `color` attribute, without anything after its closing quote
+            let color = attributes
+                .strip_prefix("color=")
+                .and_then(|value| {
+                    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+                    let value = &value[1..];
+                    match value.find(quote) {
+                        Some(end) if end == value.len() - 1 => Some(&value[..end]),
+                        _ => None,
+                    }
+                })
+                .ok_or_else(|| MarkupError::UnknownAttribute(attributes.to_string()))?;
+
+            let hex = color.strip_prefix('#').unwrap_or("");
+            if (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(format!("<span foreground=\"{}\">", color))
+            } else {
+                Err(MarkupError::InvalidColor(color.to_string()))
+            }
+        }
+        _ => Err(MarkupError::UnknownTag(name.to_string())),
+    }
+}
+
+/// Checks that the markup labels of `items` and all their subitems are valid.
+pub fn validate_markup(items: &[MenuItem]) -> Result<(), String> {
+    for item in items.iter() {
+        if let Some(markup) = &item.markup {
+            markup
+                .parse::<Markup>()
+                .map_err(|err| format!("Invalid markup `{}`: {}", markup, err))?;
+        }
+        if let Some(subitems) = &item.subitems {
+            validate_markup(subitems)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<Markup, MarkupError> {
+        s.parse()
+    }
+
+    #[test]
+    fn converts_supported_tags_to_pango() {
+        let markup = parse("<b>Bold</b>, <i>italic</i> and <code>code</code>").unwrap();
+        assert_eq!(
+            markup.to_pango(),
+            "<b>Bold</b>, <i>italic</i> and <tt>code</tt>"
+        );
+        assert_eq!(markup.text(), "Bold, italic and code");
+
+        let markup = parse("<span color='#c00'><b>Red</b></span>").unwrap();
+        assert_eq!(
+            markup.to_pango(),
+            "<span foreground=\"#c00\"><b>Red</b></span>"
+        );
+        assert_eq!(markup.text(), "Red");
+    }
+
+    #[test]
+    fn rejects_other_tags_and_attributes() {
+        assert_eq!(
+            parse("<u>Underlined</u>"),
+            Err(MarkupError::UnknownTag("u".to_string()))
+        );
+        assert_eq!(
+            parse("<span font=\"99\">Huge</span>"),
+            Err(MarkupError::UnknownAttribute("font=\"99\"".to_string()))
+        );
+        assert_eq!(
+            parse("<span color=\"#fff\" size=\"x-large\">Huge</span>"),
+            Err(MarkupError::UnknownAttribute(
+                "color=\"#fff\" size=\"x-large\"".to_string()
+            ))
+        );
+        assert_eq!(
+            parse("<b class=\"x\">Bold</b>"),
+            Err(MarkupError::UnknownAttribute("class=\"x\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn only_accepts_hex_colors() {
+        assert!(parse("<span color=\"#a0B1c2\">Hex</span>").is_ok());
+        for color in ["red", "#12", "#12345g", "#1234", "rgb(0,0,0)"] {
+            assert_eq!(
+                parse(&format!("<span color=\"{}\">Text</span>", color)),
+                Err(MarkupError::InvalidColor(color.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn decodes_entities_and_escapes_text() {
+        let markup = parse("a &lt;b&gt; &amp; &quot;c&quot; &apos;d&apos; > e").unwrap();
+        assert_eq!(markup.text(), "a <b> & \"c\" 'd' > e");
+        assert_eq!(
+            markup.to_pango(),
+            "a &lt;b&gt; &amp; &quot;c&quot; &apos;d&apos; &gt; e"
+        );
+
+        assert_eq!(
+            parse("&nbsp;"),
+            Err(MarkupError::InvalidEntity("nbsp".to_string()))
+        );
+        assert_eq!(
+            parse("&#60;"),
+            Err(MarkupError::InvalidEntity("#60".to_string()))
+        );
+        assert_eq!(
+            parse("Save & Close"),
+            Err(MarkupError::InvalidEntity(" Close".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_self_closing_tags() {
+        assert_eq!(
+            parse("<b/>"),
+            Err(MarkupError::UnknownTag("b/".to_string()))
+        );
+        assert_eq!(
+            parse("Line<br/>break"),
+            Err(MarkupError::UnknownTag("br/".to_string()))
+        );
+        assert_eq!(
+            parse("<span color=\"#fff\"/>"),
+            Err(MarkupError::UnknownAttribute("color=\"#fff\"/".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_unbalanced_nesting() {
+        assert_eq!(
+            parse("<b><i>Text</b></i>"),
+            Err(MarkupError::UnexpectedClosingTag("b".to_string()))
+        );
+        assert_eq!(
+            parse("Text</b>"),
+            Err(MarkupError::UnexpectedClosingTag("b".to_string()))
+        );
+        assert_eq!(
+            parse("<b>Text"),
+            Err(MarkupError::UnclosedTag("b".to_string()))
+        );
+        assert_eq!(parse("<b>Text</b"), Err(MarkupError::UnterminatedTag));
+    }
+}
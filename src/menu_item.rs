@@ -1,8 +1,13 @@
 use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
 
 #[derive(Clone, Deserialize)]
 pub struct MenuItem {
+    pub id: Option<String>,
     pub label: Option<String>,
+    pub label_key: Option<String>,
+    pub label_args: Option<HashMap<String, Value>>,
     pub disabled: Option<bool>,
     pub shortcut: Option<String>,
     pub event: Option<String>,
@@ -11,6 +16,12 @@ pub struct MenuItem {
     pub icon: Option<MenuItemIcon>,
     pub checked: Option<bool>,
     pub is_separator: Option<bool>,
+    pub tooltip: Option<String>,
+    pub description: Option<String>,
+    pub markup: Option<String>,
+    pub badge: Option<String>,
+    pub lazy: Option<String>,
+    pub keep_open_on_toggle: Option<bool>,
 }
 
 #[derive(Clone, Deserialize)]
@@ -20,10 +31,38 @@ pub struct MenuItemIcon {
     pub height: Option<u32>,
 }
 
+/// Changes to the item with the `id` of a menu being shown.
+#[derive(Clone, Deserialize)]
+pub struct MenuItemUpdate {
+    pub id: String,
+    pub label: Option<String>,
+    pub label_key: Option<String>,
+    pub label_args: Option<HashMap<String, Value>>,
+    pub disabled: Option<bool>,
+    pub checked: Option<bool>,
+    pub icon: Option<MenuItemIcon>,
+}
+
+impl MenuItemUpdate {
+    // An item with the label and icon of the update, to localize and resolve them
+    pub(crate) fn to_item(&self) -> MenuItem {
+        MenuItem {
+            label: self.label.clone(),
+            label_key: self.label_key.clone(),
+            label_args: self.label_args.clone(),
+            icon: self.icon.clone(),
+            ..Default::default()
+        }
+    }
+}
+
 impl Default for MenuItem {
     fn default() -> Self {
         Self {
+            id: None,
             label: None,
+            label_key: None,
+            label_args: None,
             disabled: Some(false),
             shortcut: None,
             event: None,
@@ -32,6 +71,59 @@ impl Default for MenuItem {
             icon: None,
             checked: Some(false),
             is_separator: Some(false),
+            tooltip: None,
+            description: None,
+            markup: None,
+            badge: None,
+            lazy: None,
+            keep_open_on_toggle: None,
+        }
+    }
+}
+
+// Applies the `keep_open_on_toggle` option of the menu to the items (and subitems)
+// that don't set their own
+pub(crate) fn keep_open_on_toggle(items: &mut [MenuItem], keep_open: bool) {
+    for item in items.iter_mut() {
+        item.keep_open_on_toggle.get_or_insert(keep_open);
+        if let Some(subitems) = &mut item.subitems {
+            keep_open_on_toggle(subitems, keep_open);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_option_set_on_items() {
+        let mut items = vec![
+            MenuItem {
+                checked: Some(true),
+                ..Default::default()
+            },
+            MenuItem {
+                keep_open_on_toggle: Some(false),
+                subitems: Some(vec![
+                    MenuItem {
+                        checked: Some(false),
+                        ..Default::default()
+                    },
+                    MenuItem {
+                        keep_open_on_toggle: Some(false),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            },
+        ];
+        keep_open_on_toggle(&mut items, true);
+
+        assert_eq!(items[0].keep_open_on_toggle, Some(true));
+        assert_eq!(items[1].keep_open_on_toggle, Some(false));
+        let subitems = items[1].subitems.as_ref().unwrap();
+        assert_eq!(subitems[0].keep_open_on_toggle, Some(true));
+        assert_eq!(subitems[1].keep_open_on_toggle, Some(false));
+    }
+}
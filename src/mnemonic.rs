@@ -0,0 +1,110 @@
+/// A menu item label with its access key marker parsed.
+///
+/// In `MenuItem.label` a `&` marks the next character as the access key (`&Open`)
+/// when it is a letter or a digit, `&&` is a literal `&`. Any other `&` is kept as is,
+/// so labels like `Save & Close` are unchanged. Only the first marker is used.
+pub struct Mnemonic {
+    text: String,
+    // Char index of the access key in `text`
+    index: Option<usize>,
+}
+
+impl Mnemonic {
+    pub fn parse(label: &str) -> Self {
+        let mut text = String::with_capacity(label.len());
+        let mut index = None;
+        let mut chars = label.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c == '&' {
+                match chars.peek() {
+                    Some('&') => {
+                        chars.next();
+                    }
+                    Some(next) if next.is_alphanumeric() => {
+                        if index.is_none() {
+                            index = Some(text.chars().count());
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            text.push(c);
+        }
+
+        Self { text, index }
+    }
+
+    /// The label without markers, for platforms without access keys and for filtering items.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The label with the access key marked by `_` and literal underscores doubled, as used by GTK.
+    pub fn to_gtk(&self) -> String {
+        self.marked('_')
+    }
+
+    /// The label with the access key marked by `&` and literal ampersands doubled, as used by Windows.
+    #[cfg(any(target_os = "windows", test))]
+    pub fn to_windows(&self) -> String {
+        self.marked('&')
+    }
+
+    fn marked(&self, marker: char) -> String {
+        let mut marked = String::with_capacity(self.text.len() + 1);
+        for (i, c) in self.text.chars().enumerate() {
+            if Some(i) == self.index {
+                marked.push(marker);
+            }
+            if c == marker {
+                marked.push(marker);
+            }
+            marked.push(c);
+        }
+        marked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marks_the_first_access_key() {
+        let label = Mnemonic::parse("&Open &Recent");
+        assert_eq!(label.text(), "Open Recent");
+        assert_eq!(label.to_gtk(), "_Open Recent");
+        assert_eq!(label.to_windows(), "&Open Recent");
+
+        let label = Mnemonic::parse("Save &2");
+        assert_eq!(label.text(), "Save 2");
+        assert_eq!(label.to_windows(), "Save &2");
+    }
+
+    #[test]
+    fn keeps_ampersands_that_are_not_markers() {
+        for label in ["Save & Close", "Fish &", "&"] {
+            let parsed = Mnemonic::parse(label);
+            assert_eq!(parsed.text(), label);
+            assert_eq!(parsed.to_gtk(), label);
+        }
+        assert_eq!(
+            Mnemonic::parse("Save & Close").to_windows(),
+            "Save && Close"
+        );
+    }
+
+    #[test]
+    fn escapes_literal_markers() {
+        let label = Mnemonic::parse("Rock&&Roll &Radio");
+        assert_eq!(label.text(), "Rock&Roll Radio");
+        assert_eq!(label.to_gtk(), "Rock&Roll _Radio");
+        assert_eq!(label.to_windows(), "Rock&&Roll &Radio");
+
+        let label = Mnemonic::parse("snake_&case");
+        assert_eq!(label.to_gtk(), "snake___case");
+        assert_eq!(label.to_windows(), "snake_&case");
+    }
+}
@@ -0,0 +1,193 @@
+use tauri::{Runtime, Window};
+
+use crate::MenuItem;
+
+// Label of the submenu holding the items after the limit,
+// it can be localized with this key in the catalogs
+const MORE_LABEL: &str = "More…";
+const MORE_LABEL_KEY: &str = "context_menu.more";
+
+// Approximate height of a menu item in logical pixels, used to fit menus on the screen
+#[cfg(target_os = "linux")]
+const ITEM_HEIGHT: f64 = 28.0;
+#[cfg(not(target_os = "linux"))]
+const ITEM_HEIGHT: f64 = 22.0;
+
+// Part of the monitor height left for the menu, the rest is kept for panels and the taskbar
+const USABLE_HEIGHT_RATIO: f64 = 0.9;
+
+/// How menus with more items than fit on the screen (or than the configured limit) are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Moves the items after the limit into a "More…" submenu.
+    Submenu,
+    /// Keeps all the items in the menu and lets it scroll, capped to the limit.
+    ///
+    /// Only supported on Windows, building the plugin with it fails on Linux and MacOS.
+    Scroll,
+}
+
+impl Default for Overflow {
+    fn default() -> Self {
+        Overflow::Submenu
+    }
+}
+
+pub struct OverflowConfig {
+    pub overflow: Overflow,
+    max_items: Option<usize>,
+}
+
+impl OverflowConfig {
+    pub fn new(overflow: Overflow, max_items: Option<usize>) -> Result<Self, String> {
+        // GTK and AppKit menus can't be capped to a number of items
+        #[cfg(not(target_os = "windows"))]
+        if overflow == Overflow::Scroll {
+            return Err("Overflow::Scroll is only supported on Windows".to_string());
+        }
+
+        Ok(Self {
+            overflow,
+            max_items,
+        })
+    }
+
+    /// The number of items shown in a menu (or submenu) on the monitor of `window`,
+    /// capped by the configured limit.
+    pub fn max_items<R: Runtime>(&self, window: &Window<R>) -> usize {
+        let fitting = window
+            .current_monitor()
+            .ok()
+            .flatten()
+            .map(|monitor| {
+                let height = monitor.size().height as f64 / monitor.scale_factor();
+                (height * USABLE_HEIGHT_RATIO / ITEM_HEIGHT) as usize
+            })
+            .unwrap_or(usize::MAX);
+
+        self.max_items
+            .map_or(fitting, |max_items| max_items.min(fitting))
+            .max(2)
+    }
+
+    /// The height in logical pixels of `max_items` items.
+    #[cfg(target_os = "windows")]
+    pub fn max_height(max_items: usize) -> f64 {
+        max_items as f64 * ITEM_HEIGHT
+    }
+
+    /// Moves the items of `items` and of all their submenus after `max_items`
+    /// into a "More…" submenu, unless the menus scroll.
+    pub fn apply(&self, items: &mut Vec<MenuItem>, max_items: usize) {
+        if self.overflow == Overflow::Submenu {
+            move_to_submenu(items, max_items);
+        }
+    }
+}
+
+fn move_to_submenu(items: &mut Vec<MenuItem>, max_items: usize) {
+    for item in items.iter_mut() {
+        if let Some(subitems) = &mut item.subitems {
+            move_to_submenu(subitems, max_items);
+        }
+    }
+    split_overflow(items, max_items);
+}
+
+fn split_overflow(items: &mut Vec<MenuItem>, max_items: usize) {
+    if items.len() <= max_items {
+        return;
+    }
+
+    let mut rest = items.split_off(max_items - 1);
+    // Separators at the boundary would end the menu or start the submenu
+    while items.last().map_or(false, is_separator) {
+        items.pop();
+    }
+    while rest.first().map_or(false, is_separator) {
+        rest.remove(0);
+    }
+    if rest.is_empty() {
+        return;
+    }
+
+    // The "More…" submenu can overflow as well
+    split_overflow(&mut rest, max_items);
+    items.push(MenuItem {
+        label: Some(MORE_LABEL.to_string()),
+        label_key: Some(MORE_LABEL_KEY.to_string()),
+        subitems: Some(rest),
+        disabled: Some(false),
+        checked: None,
+        is_separator: Some(false),
+        ..Default::default()
+    });
+}
+
+fn is_separator(item: &MenuItem) -> bool {
+    item.is_separator.unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(items: &[MenuItem]) -> Vec<&str> {
+        items
+            .iter()
+            .map(|item| item.label.as_deref().unwrap_or("-"))
+            .collect()
+    }
+
+    fn item(label: &str) -> MenuItem {
+        MenuItem {
+            label: Some(label.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn moves_the_remaining_items_into_nested_submenus() {
+        let mut items: Vec<MenuItem> = ["a", "b", "c", "d", "e", "f"]
+            .iter()
+            .copied()
+            .map(item)
+            .collect();
+        move_to_submenu(&mut items, 3);
+
+        assert_eq!(labels(&items), ["a", "b", MORE_LABEL]);
+        let more = items[2].subitems.as_ref().unwrap();
+        assert_eq!(labels(more), ["c", "d", MORE_LABEL]);
+        assert_eq!(labels(more[2].subitems.as_ref().unwrap()), ["e", "f"]);
+    }
+
+    #[test]
+    fn adds_a_plain_more_item() {
+        let mut items: Vec<MenuItem> = ["a", "b", "c"].iter().copied().map(item).collect();
+        move_to_submenu(&mut items, 2);
+
+        let more = &items[1];
+        assert!(more.checked.is_none());
+        assert_eq!(more.is_separator, Some(false));
+        assert_eq!(more.disabled, Some(false));
+    }
+
+    #[test]
+    fn drops_separators_at_the_boundary() {
+        let separator = MenuItem {
+            is_separator: Some(true),
+            ..Default::default()
+        };
+        let mut items = vec![
+            item("a"),
+            separator.clone(),
+            separator,
+            item("b"),
+            item("c"),
+        ];
+        move_to_submenu(&mut items, 3);
+
+        assert_eq!(labels(&items), ["a", MORE_LABEL]);
+        assert_eq!(labels(items[1].subitems.as_ref().unwrap()), ["b", "c"]);
+    }
+}
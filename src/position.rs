@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+
+use crate::direction::Direction;
+use crate::Position;
+
+/// A point of a rectangle, named after the compass direction from its center.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Gravity {
+    NorthWest,
+    North,
+    NorthEast,
+    West,
+    Center,
+    East,
+    SouthWest,
+    South,
+    SouthEast,
+}
+
+impl Gravity {
+    /// The position of the point as fractions of the width and height of the rectangle.
+    pub fn factors(self) -> (f64, f64) {
+        match self {
+            Gravity::NorthWest => (0.0, 0.0),
+            Gravity::North => (0.5, 0.0),
+            Gravity::NorthEast => (1.0, 0.0),
+            Gravity::West => (0.0, 0.5),
+            Gravity::Center => (0.5, 0.5),
+            Gravity::East => (1.0, 0.5),
+            Gravity::SouthWest => (0.0, 1.0),
+            Gravity::South => (0.5, 1.0),
+            Gravity::SouthEast => (1.0, 1.0),
+        }
+    }
+
+    fn from_factors(x: f64, y: f64) -> Gravity {
+        match (x, y) {
+            (x, y) if x < 0.5 && y < 0.5 => Gravity::NorthWest,
+            (x, y) if x < 0.5 && y > 0.5 => Gravity::SouthWest,
+            (x, _) if x < 0.5 => Gravity::West,
+            (x, y) if x > 0.5 && y < 0.5 => Gravity::NorthEast,
+            (x, y) if x > 0.5 && y > 0.5 => Gravity::SouthEast,
+            (x, _) if x > 0.5 => Gravity::East,
+            (_, y) if y < 0.5 => Gravity::North,
+            (_, y) if y > 0.5 => Gravity::South,
+            _ => Gravity::Center,
+        }
+    }
+
+    /// The point on the other side horizontally (e.g. `NorthEast` for `NorthWest`).
+    pub fn flip_x(self) -> Gravity {
+        let (x, y) = self.factors();
+        Gravity::from_factors(1.0 - x, y)
+    }
+
+    /// The point on the other side vertically (e.g. `SouthWest` for `NorthWest`).
+    pub fn flip_y(self) -> Gravity {
+        let (x, y) = self.factors();
+        Gravity::from_factors(x, 1.0 - y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// How the menu is attached to its anchor rectangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    /// Point of the anchor rectangle the menu is attached to.
+    pub rect_anchor: Gravity,
+    /// Point of the menu placed on the `rect_anchor` point.
+    pub menu_anchor: Gravity,
+    pub offset_x: f64,
+    pub offset_y: f64,
+    /// Whether the menu moves to the opposite side of the anchor when it doesn't fit.
+    pub flip: bool,
+    /// Whether the menu is shifted along the anchor to stay on the screen.
+    pub slide: bool,
+}
+
+impl Placement {
+    /// The placement given in `position`, the menu opens to the right of the position
+    /// (left for right-to-left menus) unless anchors are given.
+    pub fn new(position: Option<&Position>, direction: Direction) -> Self {
+        let default_anchor = match direction {
+            Direction::Ltr => Gravity::NorthWest,
+            Direction::Rtl => Gravity::NorthEast,
+        };
+
+        Self {
+            rect_anchor: position
+                .and_then(|position| position.rect_anchor)
+                .unwrap_or(default_anchor),
+            menu_anchor: position
+                .and_then(|position| position.menu_anchor)
+                .unwrap_or(default_anchor),
+            offset_x: position
+                .and_then(|position| position.offset_x)
+                .unwrap_or(0.0),
+            offset_y: position
+                .and_then(|position| position.offset_y)
+                .unwrap_or(0.0),
+            flip: position.and_then(|position| position.flip).unwrap_or(true),
+            slide: position.and_then(|position| position.slide).unwrap_or(true),
+        }
+    }
+
+    /// The point of `anchor` the menu is attached to, offset.
+    pub fn anchor_point(&self, anchor: &Rect) -> (f64, f64) {
+        let (x, y) = self.rect_anchor.factors();
+        (
+            anchor.x + anchor.width * x + self.offset_x,
+            anchor.y + anchor.height * y + self.offset_y,
+        )
+    }
+
+    /// Computes the top left corner of a menu of `width` and `height` attached to `anchor`,
+    /// flipped and slid to fit in `bounds` as allowed.
+    pub fn place(&self, anchor: &Rect, width: f64, height: f64, bounds: &Rect) -> (f64, f64) {
+        let (mut x, mut y) = self.origin(anchor, width, height);
+
+        if self.flip {
+            if x < bounds.x || x + width > bounds.x + bounds.width {
+                let flipped = Placement {
+                    rect_anchor: self.rect_anchor.flip_x(),
+                    menu_anchor: self.menu_anchor.flip_x(),
+                    offset_x: -self.offset_x,
+                    ..*self
+                };
+                let (flipped_x, _) = flipped.origin(anchor, width, height);
+                if overflow(flipped_x, width, bounds.x, bounds.width)
+                    < overflow(x, width, bounds.x, bounds.width)
+                {
+                    x = flipped_x;
+                }
+            }
+            if y < bounds.y || y + height > bounds.y + bounds.height {
+                let flipped = Placement {
+                    rect_anchor: self.rect_anchor.flip_y(),
+                    menu_anchor: self.menu_anchor.flip_y(),
+                    offset_y: -self.offset_y,
+                    ..*self
+                };
+                let (_, flipped_y) = flipped.origin(anchor, width, height);
+                if overflow(flipped_y, height, bounds.y, bounds.height)
+                    < overflow(y, height, bounds.y, bounds.height)
+                {
+                    y = flipped_y;
+                }
+            }
+        }
+
+        if self.slide {
+            x = slide(x, width, bounds.x, bounds.width);
+            y = slide(y, height, bounds.y, bounds.height);
+        }
+
+        (x, y)
+    }
+
+    fn origin(&self, anchor: &Rect, width: f64, height: f64) -> (f64, f64) {
+        let (anchor_x, anchor_y) = self.anchor_point(anchor);
+        let (menu_x, menu_y) = self.menu_anchor.factors();
+        (anchor_x - width * menu_x, anchor_y - height * menu_y)
+    }
+}
+
+// How much of a segment starting at `start` is outside of the bounds
+fn overflow(start: f64, length: f64, min: f64, bounds_length: f64) -> f64 {
+    (min - start).max(0.0) + (start + length - (min + bounds_length)).max(0.0)
+}
+
+// Moves a segment into the bounds, keeping its start visible if it is too long
+fn slide(start: f64, length: f64, min: f64, bounds_length: f64) -> f64 {
+    start.min(min + bounds_length - length).max(min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dropdown() -> Placement {
+        Placement {
+            rect_anchor: Gravity::SouthWest,
+            menu_anchor: Gravity::NorthWest,
+            offset_x: 0.0,
+            offset_y: 4.0,
+            flip: true,
+            slide: true,
+        }
+    }
+
+    #[test]
+    fn places_the_menu_below_the_anchor() {
+        let button = Rect::new(100.0, 10.0, 80.0, 30.0);
+        let screen = Rect::new(0.0, 0.0, 1000.0, 800.0);
+        assert_eq!(
+            dropdown().place(&button, 200.0, 300.0, &screen),
+            (100.0, 44.0)
+        );
+    }
+
+    #[test]
+    fn flips_above_the_anchor_near_the_bottom_edge() {
+        let button = Rect::new(100.0, 700.0, 80.0, 30.0);
+        let screen = Rect::new(0.0, 0.0, 1000.0, 800.0);
+        assert_eq!(
+            dropdown().place(&button, 200.0, 300.0, &screen),
+            (100.0, 396.0)
+        );
+    }
+
+    #[test]
+    fn flips_or_slides_near_the_right_edge() {
+        let button = Rect::new(900.0, 10.0, 80.0, 30.0);
+        let screen = Rect::new(0.0, 0.0, 1000.0, 800.0);
+        assert_eq!(
+            dropdown().place(&button, 200.0, 300.0, &screen),
+            (780.0, 44.0)
+        );
+
+        let slide_only = Placement {
+            flip: false,
+            ..dropdown()
+        };
+        assert_eq!(
+            slide_only.place(&button, 200.0, 300.0, &screen),
+            (800.0, 44.0)
+        );
+    }
+
+    #[test]
+    fn stays_in_a_work_area_not_starting_at_the_origin() {
+        // Screen with a 30px top panel, menu opened upwards from a point near the top
+        let point = Rect::new(500.0, 40.0, 0.0, 0.0);
+        let work_area = Rect::new(0.0, 30.0, 1000.0, 770.0);
+        let upwards = Placement {
+            rect_anchor: Gravity::NorthWest,
+            menu_anchor: Gravity::SouthWest,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            flip: false,
+            slide: true,
+        };
+        assert_eq!(
+            upwards.place(&point, 200.0, 300.0, &work_area),
+            (500.0, 30.0)
+        );
+    }
+}
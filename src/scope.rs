@@ -0,0 +1,206 @@
+use glob::{MatchOptions, Pattern};
+use std::path::{Component, Path, PathBuf};
+use tauri::PathResolver;
+
+// Scope used when the plugin is initialized without an explicit one
+pub const DEFAULT_ICON_SCOPE: &str = "$RESOURCE/**";
+
+/// Set of glob patterns restricting which files can be loaded as menu item icons.
+///
+/// Patterns may start with one of the `$RESOURCE`, `$APPCONFIG`, `$APPDATA`,
+/// `$APPLOCALDATA`, `$APPCACHE` or `$APPLOG` variables, which are replaced with
+/// the matching app directory.
+pub struct IconScope {
+    patterns: Vec<Pattern>,
+}
+
+impl IconScope {
+    pub fn new(patterns: &[String], resolver: &PathResolver) -> Result<Self, String> {
+        let mut expanded = Vec::new();
+        for pattern in patterns.iter() {
+            let dirs = expand_variables(pattern, |variable| app_dir(variable, resolver))?;
+            for dir in dirs {
+                expanded.push(
+                    Pattern::new(&dir).map_err(|err| {
+                        format!("Invalid icon scope pattern {}: {}", pattern, err)
+                    })?,
+                );
+            }
+        }
+
+        Ok(Self { patterns: expanded })
+    }
+
+    /// Returns the canonical path of the file at `path`, or an error unless it is an existing
+    /// file inside the scope.
+    ///
+    /// Missing files and files outside of the scope fail with the same error, so the result
+    /// doesn't tell whether a file exists outside of the scope.
+    pub fn check(&self, path: &Path) -> Result<PathBuf, String> {
+        let not_allowed = || {
+            format!(
+                "Icon path is not allowed by the icon scope: {}",
+                path.display()
+            )
+        };
+
+        // Check the path before touching the file system, without `..` components
+        if !path.is_absolute() || !self.allows(&normalize(path)) {
+            return Err(not_allowed());
+        }
+
+        // Then the file it points to, so symlinks can't be used to leave the scope
+        match path.canonicalize() {
+            Ok(canonical) if canonical.is_file() && self.allows(&canonical) => Ok(canonical),
+            _ => Err(not_allowed()),
+        }
+    }
+
+    fn allows(&self, path: &Path) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches_path_with(path, options))
+    }
+}
+
+// Resolves `..` and `.` components without following symlinks
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn app_dir(variable: &str, resolver: &PathResolver) -> Option<PathBuf> {
+    match variable {
+        "$RESOURCE" => resolver.resource_dir(),
+        "$APPCONFIG" => resolver.app_config_dir(),
+        "$APPDATA" => resolver.app_data_dir(),
+        "$APPLOCALDATA" => resolver.app_local_data_dir(),
+        "$APPCACHE" => resolver.app_cache_dir(),
+        "$APPLOG" => resolver.app_log_dir(),
+        _ => None,
+    }
+}
+
+// Expands the directory variable `pattern` starts with, if any, using `resolve` to find it.
+// Both the directory and its canonical form are returned, as icon paths are matched
+// before and after following symlinks.
+fn expand_variables<F>(pattern: &str, resolve: F) -> Result<Vec<String>, String>
+where
+    F: Fn(&str) -> Option<PathBuf>,
+{
+    const VARIABLES: [&str; 6] = [
+        "$RESOURCE",
+        "$APPCONFIG",
+        "$APPDATA",
+        "$APPLOCALDATA",
+        "$APPCACHE",
+        "$APPLOG",
+    ];
+
+    for variable in VARIABLES.iter() {
+        if let Some(rest) = pattern.strip_prefix(variable) {
+            let dir = resolve(variable)
+                .ok_or_else(|| format!("Failed to resolve {} for the icon scope", variable))?;
+            let mut expanded = vec![Pattern::escape(&dir.to_string_lossy()) + rest];
+            if let Ok(canonical) = dir.canonicalize() {
+                let canonical = Pattern::escape(&canonical.to_string_lossy()) + rest;
+                if canonical != expanded[0] {
+                    expanded.push(canonical);
+                }
+            }
+            return Ok(expanded);
+        }
+    }
+
+    Ok(vec![pattern.to_string()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn scope(patterns: &[&str]) -> IconScope {
+        IconScope {
+            patterns: patterns
+                .iter()
+                .map(|pattern| Pattern::new(pattern).unwrap())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn expands_directory_variables() {
+        let resolve = |variable: &str| match variable {
+            "$RESOURCE" => Some(PathBuf::from("/app/[resources]")),
+            _ => None,
+        };
+
+        assert_eq!(
+            expand_variables("$RESOURCE/**", resolve),
+            Ok(vec!["/app/[[]resources[]]/**".to_string()])
+        );
+        assert_eq!(
+            expand_variables("/usr/share/icons/*.png", resolve),
+            Ok(vec!["/usr/share/icons/*.png".to_string()])
+        );
+        assert_eq!(
+            expand_variables("$APPDATA/icons/*.png", resolve),
+            Err("Failed to resolve $APPDATA for the icon scope".to_string())
+        );
+    }
+
+    #[test]
+    fn matches_paths_without_crossing_directories() {
+        let scope = scope(&["/app/resources/**", "/app/data/icons/*.png"]);
+
+        assert!(scope.allows(Path::new("/app/resources/icons/nested/copy.png")));
+        assert!(scope.allows(Path::new("/app/data/icons/copy.png")));
+        assert!(!scope.allows(Path::new("/app/data/icons/nested/copy.png")));
+        assert!(!scope.allows(Path::new("/app/data/copy.png")));
+    }
+
+    #[test]
+    fn rejects_parent_components_before_touching_the_file_system() {
+        let scope = scope(&["/app/resources/**"]);
+
+        assert!(scope.allows(&normalize(Path::new("/app/resources/./icons/copy.png"))));
+        assert!(!scope.allows(&normalize(Path::new("/app/resources/../../etc/passwd"))));
+        assert!(scope.check(Path::new("icons/copy.png")).is_err());
+    }
+
+    #[test]
+    fn fails_the_same_way_for_missing_and_out_of_scope_files() {
+        let dir = std::env::temp_dir().join(format!("context-menu-scope-{}", std::process::id()));
+        fs::create_dir_all(dir.join("icons")).unwrap();
+        fs::write(dir.join("icons/copy.png"), b"").unwrap();
+        fs::write(dir.join("secret.png"), b"").unwrap();
+        let dir = dir.canonicalize().unwrap();
+        let scope = scope(&[&format!(
+            "{}/icons/*.png",
+            Pattern::escape(&dir.to_string_lossy())
+        )]);
+
+        assert_eq!(
+            scope.check(&dir.join("icons/../icons/copy.png")),
+            Ok(dir.join("icons/copy.png"))
+        );
+        let missing = scope.check(&dir.join("icons/missing.png")).unwrap_err();
+        let outside = scope.check(&dir.join("secret.png")).unwrap_err();
+        assert!(missing.starts_with("Icon path is not allowed by the icon scope"));
+        assert!(outside.starts_with("Icon path is not allowed by the icon scope"));
+    }
+}
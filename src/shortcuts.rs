@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::accelerator::Accelerator;
+use crate::MenuItem;
+
+struct Registration {
+    accelerator: Accelerator,
+    event: String,
+    payload: Option<String>,
+}
+
+// Registered shortcuts by window label and menu name.
+lazy_static::lazy_static! {
+    static ref REGISTRY: Mutex<HashMap<(String, String), Vec<Registration>>> = Mutex::new(HashMap::new());
+}
+
+/// Registers the shortcuts of the enabled `items` (and their subitems) that have an event,
+/// replacing the ones previously registered for the same `menu` of the window.
+///
+/// Returns the key presses triggering the registered shortcuts, see [`Accelerator::key_press`].
+pub fn register(window: &str, menu: &str, items: &[MenuItem]) -> Result<Vec<String>, String> {
+    let mut registrations = Vec::new();
+    collect(items, &mut registrations)?;
+
+    let key_presses = registrations
+        .iter()
+        .map(|registration| registration.accelerator.key_press())
+        .collect();
+    REGISTRY
+        .lock()
+        .unwrap()
+        .insert((window.to_string(), menu.to_string()), registrations);
+    Ok(key_presses)
+}
+
+/// Removes the shortcuts registered for `menu` of the window.
+pub fn unregister(window: &str, menu: &str) {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .remove(&(window.to_string(), menu.to_string()));
+}
+
+/// Removes all the shortcuts registered for the window.
+pub fn unregister_window(window: &str) {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .retain(|(label, _), _| label != window);
+}
+
+/// Returns the event and payload registered for `accelerator` in the window, if any.
+pub fn find(window: &str, accelerator: Accelerator) -> Option<(String, Option<String>)> {
+    let accelerator = accelerator.normalized();
+
+    REGISTRY
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|((label, _), _)| label == window)
+        .flat_map(|(_, registrations)| registrations.iter())
+        .find(|registration| registration.accelerator == accelerator)
+        .map(|registration| (registration.event.clone(), registration.payload.clone()))
+}
+
+fn collect(items: &[MenuItem], registrations: &mut Vec<Registration>) -> Result<(), String> {
+    for item in items.iter().filter(|item| !item.disabled.unwrap_or(false)) {
+        if let (Some(shortcut), Some(event)) = (&item.shortcut, &item.event) {
+            let accelerator = shortcut
+                .parse::<Accelerator>()
+                .map_err(|err| format!("Invalid shortcut `{}`: {}", shortcut, err))?;
+            registrations.push(Registration {
+                accelerator: accelerator.normalized(),
+                event: event.clone(),
+                payload: item.payload.clone(),
+            });
+        }
+        if let Some(subitems) = &item.subitems {
+            collect(subitems, registrations)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(shortcut: &str, event: &str) -> MenuItem {
+        MenuItem {
+            shortcut: Some(shortcut.to_string()),
+            event: Some(event.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn shortcut(s: &str) -> Accelerator {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn finds_the_events_of_enabled_items_in_subitems() {
+        let disabled = MenuItem {
+            disabled: Some(true),
+            ..item("ctrl+d", "duplicate")
+        };
+        let parent = MenuItem {
+            subitems: Some(vec![item("ctrl+shift+k", "delete_line"), disabled]),
+            ..Default::default()
+        };
+        let key_presses =
+            register("find", "editor", &[item("cmd_or_ctrl+c", "copy"), parent]).unwrap();
+
+        assert_eq!(key_presses.len(), 2);
+        assert_eq!(
+            find("find", shortcut("cmd_or_ctrl+C")),
+            Some(("copy".to_string(), None))
+        );
+        assert_eq!(
+            find("find", shortcut("shift+ctrl+k")),
+            Some(("delete_line".to_string(), None))
+        );
+        assert_eq!(find("find", shortcut("ctrl+d")), None);
+        assert_eq!(find("other", shortcut("cmd_or_ctrl+c")), None);
+    }
+
+    #[test]
+    fn replaces_and_removes_registrations() {
+        register("remove", "editor", &[item("ctrl+c", "copy")]).unwrap();
+        register("remove", "editor", &[item("ctrl+x", "cut")]).unwrap();
+        register("remove", "list", &[item("delete", "remove")]).unwrap();
+        assert_eq!(find("remove", shortcut("ctrl+c")), None);
+        assert!(find("remove", shortcut("ctrl+x")).is_some());
+
+        unregister("remove", "editor");
+        assert_eq!(find("remove", shortcut("ctrl+x")), None);
+        assert!(find("remove", shortcut("delete")).is_some());
+
+        unregister_window("remove");
+        assert_eq!(find("remove", shortcut("delete")), None);
+    }
+
+    #[test]
+    fn rejects_invalid_shortcuts() {
+        assert_eq!(
+            register("invalid", "editor", &[item("ctrl+foo", "copy")]),
+            Err("Invalid shortcut `ctrl+foo`: unknown key `foo`".to_string())
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+/// How the user opened the menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Trigger {
+    Pointer,
+    /// The Menu key or Shift+F10, the menu opens at the focused element with its first
+    /// item selected.
+    Keyboard,
+}
+
+impl Trigger {
+    pub fn from_str(s: &str) -> Option<Trigger> {
+        match s {
+            "pointer" => Some(Trigger::Pointer),
+            "keyboard" => Some(Trigger::Keyboard),
+            _ => None,
+        }
+    }
+}
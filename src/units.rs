@@ -0,0 +1,66 @@
+use serde::Deserialize;
+
+/// Unit of the coordinates of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Units {
+    /// CSS pixels of the web page, affected by the zoom level of the webview.
+    Css,
+    /// Logical pixels of the window, affected by the scale of the monitor.
+    Logical,
+    /// Pixels of the screen.
+    Physical,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Units::Logical
+    }
+}
+
+impl Units {
+    /// The factor converting coordinates in these units to logical pixels,
+    /// given the `scale_factor` of the monitor and the `zoom` level of the webview.
+    pub fn to_logical(self, scale_factor: f64, zoom: f64) -> f64 {
+        match self {
+            Units::Css => zoom,
+            Units::Logical => 1.0,
+            Units::Physical => 1.0 / scale_factor,
+        }
+    }
+}
+
+/// The zoom level of the webview, from the zoom reported by the webview if any,
+/// otherwise from the `device_pixel_ratio` of the web page, which includes the zoom
+/// level on Windows.
+pub fn zoom_level(
+    webview_zoom: Option<f64>,
+    device_pixel_ratio: Option<f64>,
+    scale_factor: f64,
+) -> f64 {
+    webview_zoom
+        .or_else(|| device_pixel_ratio.map(|ratio| ratio / scale_factor))
+        .filter(|zoom| zoom.is_finite() && *zoom > 0.0)
+        .unwrap_or(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_to_logical_pixels() {
+        // 125% zoom on a 150% monitor
+        let zoom = zoom_level(None, Some(1.875), 1.5);
+        assert_eq!(zoom, 1.25);
+        assert_eq!(100.0 * Units::Css.to_logical(1.5, zoom), 125.0);
+        assert_eq!(100.0 * Units::Logical.to_logical(1.5, zoom), 100.0);
+        assert_eq!(150.0 * Units::Physical.to_logical(1.5, zoom), 100.0);
+    }
+
+    #[test]
+    fn prefers_the_zoom_of_the_webview() {
+        assert_eq!(zoom_level(Some(0.8), Some(2.0), 2.0), 0.8);
+        assert_eq!(zoom_level(None, None, 2.0), 1.0);
+    }
+}
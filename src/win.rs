@@ -2,67 +2,143 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 use std::ptr::null_mut;
 use std::sync::{Arc, Mutex};
-use tauri::{Runtime, Window};
+use tauri::{Manager, Runtime, Window};
 use winapi::{
-    shared::minwindef::LOWORD,
-    shared::windef::{HMENU, HWND, HWND__, POINT},
+    shared::minwindef::{BOOL, LOWORD, LPARAM, TRUE, WPARAM},
+    shared::windef::{HBITMAP, HMENU, HWND, HWND__, POINT, RECT},
     um::winuser::{
-        AppendMenuW, ClientToScreen, CreatePopupMenu, DestroyMenu, DispatchMessageW, GetCursorPos,
-        GetMessageW, PostQuitMessage, SetMenuItemBitmaps, TrackPopupMenu, TranslateMessage,
-        MF_BYCOMMAND, MF_CHECKED, MF_DISABLED, MF_ENABLED, MF_POPUP, MF_SEPARATOR, MF_STRING, MSG,
-        TPM_LEFTALIGN, TPM_RIGHTBUTTON, TPM_TOPALIGN, WM_ACTIVATE, WM_COMMAND,
+        AppendMenuW, CheckMenuItem, ClientToScreen, CreatePopupMenu, DestroyMenu, DispatchMessageW,
+        EnableMenuItem, EnumThreadWindows, GetClassNameW, GetCursorPos, GetMenuItemCount,
+        GetMenuState, GetMessageW, GetWindowThreadProcessId, InvalidateRect, PostMessageW,
+        PostQuitMessage, SetMenuInfo, SetMenuItemBitmaps, SetMenuItemInfoW, TrackPopupMenuEx,
+        TranslateMessage, MENUINFO, MENUITEMINFOW, MF_BYPOSITION, MF_CHECKED, MF_DISABLED,
+        MF_ENABLED, MF_POPUP, MF_SEPARATOR, MF_STRING, MF_UNCHECKED, MIIM_STRING,
+        MIM_APPLYTOSUBMENUS, MIM_MAXHEIGHT, MSG, TPMPARAMS, TPM_BOTTOMALIGN, TPM_CENTERALIGN,
+        TPM_LAYOUTRTL, TPM_LEFTALIGN, TPM_RETURNCMD, TPM_RIGHTALIGN, TPM_RIGHTBUTTON, TPM_TOPALIGN,
+        TPM_VCENTERALIGN, TPM_VERTICAL, VK_DOWN, WM_ACTIVATE, WM_CANCELMODE, WM_KEYDOWN,
     },
 };
 
-use crate::keymap::get_key_map;
+use crate::accelerator::{Accelerator, Key};
+use crate::close::{CloseReason, MenuClosed};
+use crate::direction::Direction;
+use crate::keymap::{get_key_map, get_modifier_labels};
+use crate::markup::Markup;
+use crate::mnemonic::Mnemonic;
+use crate::overflow::{Overflow, OverflowConfig};
+use crate::position::{Placement, Rect};
 use crate::theme::Theme;
+use crate::trigger::Trigger;
 use crate::win_image_handler::{convert_to_hbitmap, load_bitmap_from_file};
-use crate::{MenuItem, Position};
+use crate::{MenuItem, MenuItemIcon, MenuItemUpdate, Position};
 
 const ID_MENU_ITEM_BASE: u32 = 1000;
 const WA_INACTIVE: u16 = 0;
+// Class of the windows showing popup menus
+const MENU_WINDOW_CLASS: &str = "#32768";
+
+// Where an item with an id is, and the parts of its label besides the text
+#[derive(Clone)]
+struct ItemRef {
+    menu: usize,
+    position: u32,
+    shortcut: Option<String>,
+    badge: Option<String>,
+}
+
+// Events by command id, items with an id and check items kept open when toggled of a menu
+#[derive(Clone, Default)]
+struct MenuEntries {
+    callbacks: HashMap<u32, (String, Option<String>)>,
+    items: HashMap<String, ItemRef>,
+    // Menu and position of the items by command id
+    toggles: HashMap<u32, (usize, u32)>,
+}
+
+// A menu created with `create_menu`
+struct CreatedMenu {
+    menu: usize,
+    entries: MenuEntries,
+    direction: Direction,
+}
+
+// A menu being shown, with the reason it will be closed for
+struct OpenMenu {
+    hwnd: usize,
+    menu: usize,
+    reason: CloseReason,
+    // Whether the menu is destroyed once closed
+    destroy: bool,
+}
 
 // We use a lazy_static Mutex to ensure thread safety.
 // This will store a map from menu item IDs to events.
 lazy_static::lazy_static! {
     static ref CALLBACK_MAP: Mutex<HashMap<u32, (String, Option<String>)>> = Mutex::new(HashMap::new());
+    // Items of the menu being shown, by id
+    static ref ITEMS: Mutex<HashMap<String, ItemRef>> = Mutex::new(HashMap::new());
+    // Menus being shown, by window label
+    static ref OPEN_MENUS: Mutex<HashMap<String, OpenMenu>> = Mutex::new(HashMap::new());
+    // Menus created with `create_menu`, by handle
+    static ref CREATED_MENUS: Mutex<HashMap<u32, CreatedMenu>> = Mutex::new(HashMap::new());
 }
 
-pub fn get_label_with_shortcut(label: &str, shortcut: Option<&str>) -> String {
-    let key_map = get_key_map();
-
-    label.to_string()
-        + &shortcut.map_or_else(String::new, |s| {
-            format!(
-                "\t{}",
-                s.split('+')
-                    .map(|part| {
-                        let mut c = part.chars();
-                        // If the part exists in the key_map, use the key_map value.
-                        // Otherwise, use the original logic.
-                        key_map.get(part).map_or_else(
-                            || c.next().unwrap_or_default().to_uppercase().to_string() + c.as_str(),
-                            |value| value.to_string(),
-                        )
-                    })
-                    .collect::<Vec<_>>()
-                    .join("+")
-            )
-        })
+pub fn get_label_with_shortcut(label: &str, shortcut: Option<&str>, badge: Option<&str>) -> String {
+    // The text after the tab is right-aligned, the badge goes before the shortcut
+    let right_column: Vec<String> = badge
+        .map(|badge| badge.replace('&', "&&"))
+        .into_iter()
+        .chain(
+            shortcut
+                .and_then(|s| s.parse::<Accelerator>().ok())
+                .map(|accelerator| get_accelerator_label(&accelerator)),
+        )
+        .collect();
+
+    if right_column.is_empty() {
+        label.to_string()
+    } else {
+        format!("{}\t{}", label, right_column.join("   "))
+    }
+}
+
+fn get_accelerator_label(accelerator: &Accelerator) -> String {
+    let key = match accelerator.key {
+        Key::Char(c) => c.to_uppercase().to_string(),
+        Key::Named(name) => get_key_map()
+            .get(name)
+            .map_or_else(|| name.to_string(), |label| label.to_string()),
+    };
+
+    let mut parts = get_modifier_labels(&accelerator.modifiers);
+    parts.push(&key);
+    parts.join("+")
 }
 
-fn append_menu_item(menu: HMENU, item: &MenuItem, counter: &mut u32) -> Result<u32, String> {
+fn append_menu_item(
+    menu: HMENU,
+    item: &MenuItem,
+    counter: &mut u32,
+    scale_factor: f64,
+    entries: &mut MenuEntries,
+) -> Result<u32, String> {
     let id = *counter;
     *counter += 1;
 
+    let position = unsafe { GetMenuItemCount(menu) } as u32;
+
     if item.is_separator.unwrap_or(false) {
         unsafe {
             AppendMenuW(menu, MF_SEPARATOR, 0, null_mut());
         }
     } else {
-        let label = item.label.as_deref().unwrap_or("");
+        // Markup labels fall back to their plain text
+        let label = match item.markup.as_deref().map(str::parse::<Markup>) {
+            Some(Ok(markup)) => markup.text().replace('&', "&&"),
+            _ => Mnemonic::parse(item.label.as_deref().unwrap_or("")).to_windows(),
+        };
         let shortcut = item.shortcut.as_deref();
-        let menu_label = get_label_with_shortcut(label, shortcut);
+        let menu_label = get_label_with_shortcut(&label, shortcut, item.badge.as_deref());
         let label_wide: Vec<u16> = menu_label
             .encode_utf16()
             .chain(std::iter::once(0))
@@ -84,7 +160,7 @@ fn append_menu_item(menu: HMENU, item: &MenuItem, counter: &mut u32) -> Result<u
         if let Some(subitems) = &item.subitems {
             let submenu = unsafe { CreatePopupMenu() };
             for subitem in subitems.iter() {
-                let _ = append_menu_item(submenu, subitem, counter);
+                let _ = append_menu_item(submenu, subitem, counter, scale_factor, entries);
             }
             unsafe {
                 AppendMenuW(
@@ -102,33 +178,37 @@ fn append_menu_item(menu: HMENU, item: &MenuItem, counter: &mut u32) -> Result<u
 
         // If an event is provided, store it in the callback map
         if let Some(event) = &item.event {
-            CALLBACK_MAP
-                .lock()
-                .unwrap()
+            entries
+                .callbacks
                 .insert(id, (event.clone(), item.payload.clone()));
         }
 
+        // Check items toggled without closing the menu
+        if item.checked.is_some()
+            && item.keep_open_on_toggle.unwrap_or(false)
+            && item.subitems.is_none()
+        {
+            entries.toggles.insert(id, (menu as usize, position));
+        }
+
+        // Items with an id can be found again to update them
+        if let Some(item_id) = &item.id {
+            entries.items.insert(
+                item_id.clone(),
+                ItemRef {
+                    menu: menu as usize,
+                    position,
+                    shortcut: item.shortcut.clone(),
+                    badge: item.badge.clone(),
+                },
+            );
+        }
+
         // If the icon path is provided, load the bitmap and set it for the menu item.
         if let Some(icon) = &item.icon {
-            match load_bitmap_from_file(&icon.path, icon.width, icon.height) {
-                Ok(bitmap) => match convert_to_hbitmap(bitmap) {
-                    Ok(hbitmap) => {
-                        if !hbitmap.is_null() {
-                            unsafe {
-                                SetMenuItemBitmaps(menu, id as u32, MF_BYCOMMAND, hbitmap, hbitmap);
-                            }
-                        } else {
-                            return Err(format!("Failed to load bitmap from path: {}", icon.path));
-                        }
-                    }
-                    Err(err_msg) => return Err(err_msg),
-                },
-                Err(err) => {
-                    return Err(format!(
-                        "Failed to load image from path: {}. Error: {:?}",
-                        icon.path, err
-                    ))
-                }
+            let hbitmap = icon_bitmap(icon, scale_factor)?;
+            unsafe {
+                SetMenuItemBitmaps(menu, position, MF_BYPOSITION, hbitmap, hbitmap);
             }
         }
     }
@@ -136,48 +216,275 @@ fn append_menu_item(menu: HMENU, item: &MenuItem, counter: &mut u32) -> Result<u
     Ok(id)
 }
 
-// This function would be called when a WM_COMMAND message is received, with the ID of the menu item that was clicked
+fn icon_bitmap(icon: &MenuItemIcon, scale_factor: f64) -> Result<HBITMAP, String> {
+    // Size the bitmap in physical pixels
+    let width = (icon.width.unwrap_or(16) as f64 * scale_factor).round() as u32;
+    let height = (icon.height.unwrap_or(16) as f64 * scale_factor).round() as u32;
+    match load_bitmap_from_file(&icon.path, Some(width), Some(height)) {
+        Ok(bitmap) => {
+            let hbitmap = convert_to_hbitmap(bitmap)?;
+            if hbitmap.is_null() {
+                Err(format!("Failed to load bitmap from path: {}", icon.path))
+            } else {
+                Ok(hbitmap)
+            }
+        }
+        Err(err) => Err(format!(
+            "Failed to load image from path: {}. Error: {:?}",
+            icon.path, err
+        )),
+    }
+}
+
+fn update_menu_item(item: &ItemRef, update: &MenuItemUpdate, scale_factor: f64) {
+    let menu = item.menu as HMENU;
+    unsafe {
+        if let Some(label) = &update.label {
+            let menu_label = get_label_with_shortcut(
+                &Mnemonic::parse(label).to_windows(),
+                item.shortcut.as_deref(),
+                item.badge.as_deref(),
+            );
+            let mut label_wide: Vec<u16> = menu_label
+                .encode_utf16()
+                .chain(std::iter::once(0))
+                .collect();
+            let mut info: MENUITEMINFOW = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<MENUITEMINFOW>() as u32;
+            info.fMask = MIIM_STRING;
+            info.dwTypeData = label_wide.as_mut_ptr();
+            SetMenuItemInfoW(menu, item.position, TRUE, &info);
+        }
+        if let Some(disabled) = update.disabled {
+            let state = if disabled { MF_DISABLED } else { MF_ENABLED };
+            EnableMenuItem(menu, item.position, MF_BYPOSITION | state);
+        }
+        if let Some(checked) = update.checked {
+            let state = if checked { MF_CHECKED } else { MF_UNCHECKED };
+            CheckMenuItem(menu, item.position, MF_BYPOSITION | state);
+        }
+        if let Some(icon) = &update.icon {
+            match icon_bitmap(icon, scale_factor) {
+                Ok(hbitmap) => {
+                    SetMenuItemBitmaps(menu, item.position, MF_BYPOSITION, hbitmap, hbitmap);
+                }
+                Err(err) => eprintln!("{}", err),
+            }
+        }
+    }
+}
+
+// This function is called with the ID of the menu item that was clicked
 pub fn handle_menu_item_click<R: Runtime>(id: u32, window: Window<R>) {
     if let Some((event, payload)) = CALLBACK_MAP.lock().unwrap().get(&id) {
         window.emit(event, &payload).unwrap(); // Emit the event to JavaScript
     }
 }
 
+// Updates the items of the menu created with `handle`, or of the menu shown in `window`,
+// and repaints the menu being shown
+pub fn update_context_menu<R: Runtime>(
+    window: &Window<R>,
+    handle: Option<u32>,
+    updates: Vec<MenuItemUpdate>,
+) {
+    let scale_factor = window.scale_factor().unwrap_or(1.0);
+    let items = match handle {
+        Some(handle) => match CREATED_MENUS.lock().unwrap().get(&handle) {
+            Some(created) => created.entries.items.clone(),
+            None => return,
+        },
+        None => ITEMS.lock().unwrap().clone(),
+    };
+    for update in updates.iter() {
+        match items.get(&update.id) {
+            Some(item) => update_menu_item(item, update, scale_factor),
+            None => eprintln!("No menu item with id {}", update.id),
+        }
+    }
+
+    // The open menu (and submenus) only show the changes once repainted, they are shown
+    // by windows of the thread owning the window, not of other apps
+    if let Ok(hwnd) = window.hwnd() {
+        unsafe {
+            let thread = GetWindowThreadProcessId(hwnd.0 as HWND, null_mut());
+            EnumThreadWindows(thread, Some(repaint_menu_window), 0);
+        }
+    }
+}
+
+unsafe extern "system" fn repaint_menu_window(hwnd: HWND, _: LPARAM) -> BOOL {
+    let mut class = [0u16; 16];
+    let len = GetClassNameW(hwnd, class.as_mut_ptr(), class.len() as i32);
+    if len > 0 && String::from_utf16_lossy(&class[..len as usize]) == MENU_WINDOW_CLASS {
+        InvalidateRect(hwnd, null_mut(), TRUE);
+    }
+    // Continue with the windows of submenus
+    TRUE
+}
+
+// Closes the menu shown in `window`, the message is handled by the menu loop
+// on the thread of the window
+pub fn hide_context_menu<R: Runtime>(window: &Window<R>) {
+    if let Some(open_menu) = OPEN_MENUS.lock().unwrap().get_mut(window.label()) {
+        open_menu.reason = CloseReason::Programmatic;
+        unsafe {
+            PostMessageW(open_menu.hwnd as HWND, WM_CANCELMODE, 0, 0);
+        }
+    }
+}
+
+pub fn create_menu<R: Runtime>(
+    window: &Window<R>,
+    handle: u32,
+    items: Vec<MenuItem>,
+    _theme: Option<Theme>,
+    direction: Direction,
+    _filter: bool,
+) {
+    let (menu, entries) = build_menu(window, Some(items));
+    CREATED_MENUS.lock().unwrap().insert(
+        handle,
+        CreatedMenu {
+            menu: menu as usize,
+            entries,
+            direction,
+        },
+    );
+}
+
+pub fn show_menu<R: Runtime>(
+    window: Window<R>,
+    handle: u32,
+    pos: Option<Position>,
+    trigger: Trigger,
+) {
+    let (menu, entries, direction) = match CREATED_MENUS.lock().unwrap().get(&handle) {
+        Some(created) => (
+            created.menu as HMENU,
+            created.entries.clone(),
+            created.direction,
+        ),
+        None => return,
+    };
+    track_menu(window, menu, entries, pos, direction, trigger, false);
+}
+
+pub fn destroy_menu(handle: u32) {
+    let created = match CREATED_MENUS.lock().unwrap().remove(&handle) {
+        Some(created) => created,
+        None => return,
+    };
+    // A menu being shown is destroyed once it is closed
+    let mut open_menus = OPEN_MENUS.lock().unwrap();
+    match open_menus
+        .values_mut()
+        .find(|open_menu| open_menu.menu == created.menu)
+    {
+        Some(open_menu) => {
+            open_menu.reason = CloseReason::Programmatic;
+            open_menu.destroy = true;
+            unsafe {
+                PostMessageW(open_menu.hwnd as HWND, WM_CANCELMODE, 0, 0);
+            }
+        }
+        None => unsafe {
+            DestroyMenu(created.menu as HMENU);
+        },
+    }
+}
+
+// Lazy submenus are filled before the menu is shown on Windows
+pub fn fill_submenu(_request: u32, _items: Vec<MenuItem>) {}
+
+// WebView2 doesn't expose its zoom level here, the device pixel ratio of the page includes it
+pub fn webview_zoom<R: Runtime>(_window: &Window<R>) -> Option<f64> {
+    None
+}
+
 pub fn show_context_menu<R: Runtime>(
     window: Window<R>,
     pos: Option<Position>,
     items: Option<Vec<MenuItem>>,
     _theme: Option<Theme>,
+    direction: Direction,
+    _filter: bool,
+    trigger: Trigger,
 ) {
-    // Clear the callback map at the start of each context menu display
-    CALLBACK_MAP.lock().unwrap().clear();
+    let (menu, entries) = build_menu(&window, items);
+    track_menu(window, menu, entries, pos, direction, trigger, true);
+}
 
+fn build_menu<R: Runtime>(
+    window: &Window<R>,
+    items: Option<Vec<MenuItem>>,
+) -> (HMENU, MenuEntries) {
     let menu = unsafe { CreatePopupMenu() };
-    let hwnd = window.hwnd().unwrap().0 as *mut HWND__;
+    let mut entries = MenuEntries::default();
+
+    let scale_factor = window.scale_factor().unwrap_or(1.0); // Use 1.0 as a default if getting the scale factor fails
 
     let mut counter = ID_MENU_ITEM_BASE;
     if let Some(menu_items) = items {
         for item in menu_items.iter() {
-            let _ = append_menu_item(menu, item, &mut counter);
+            let _ = append_menu_item(menu, item, &mut counter, scale_factor, &mut entries);
         }
     }
 
-    let position = match pos {
+    // Scroll long menus instead of splitting them into columns
+    let overflow = window.state::<OverflowConfig>();
+    if overflow.overflow == Overflow::Scroll {
+        let max_height = OverflowConfig::max_height(overflow.max_items(window)) * scale_factor;
+        let mut menu_info: MENUINFO = unsafe { std::mem::zeroed() };
+        menu_info.cbSize = std::mem::size_of::<MENUINFO>() as u32;
+        menu_info.fMask = MIM_MAXHEIGHT | MIM_APPLYTOSUBMENUS;
+        menu_info.cyMax = max_height as u32;
+        unsafe {
+            SetMenuInfo(menu, &menu_info);
+        }
+    }
+
+    (menu, entries)
+}
+
+// Shows the menu until it is closed, then destroys it if `destroy` is set
+fn track_menu<R: Runtime>(
+    window: Window<R>,
+    menu: HMENU,
+    entries: MenuEntries,
+    pos: Option<Position>,
+    direction: Direction,
+    trigger: Trigger,
+    destroy: bool,
+) {
+    // Replace the events and items of the previous menu
+    let toggles = entries.toggles;
+    *CALLBACK_MAP.lock().unwrap() = entries.callbacks;
+    *ITEMS.lock().unwrap() = entries.items;
+
+    let hwnd = window.hwnd().unwrap().0 as *mut HWND__;
+    let scale_factor = window.scale_factor().unwrap_or(1.0);
+
+    // Anchor rectangle in screen coordinates, the cursor position if none is given
+    let placement = Placement::new(pos.as_ref(), direction);
+    let anchor = match pos {
         Some(p) => {
-            let scale_factor = window.scale_factor().unwrap_or(1.0); // Use 1.0 as a default if getting the scale factor fails
-            let mut point = POINT {
+            let mut origin = POINT {
                 x: (p.x * scale_factor) as i32,
                 y: (p.y * scale_factor) as i32,
             };
 
-            if p.is_absolute.unwrap_or(false) {
-                point
-            } else {
+            if !p.is_absolute.unwrap_or(false) {
                 unsafe {
-                    ClientToScreen(hwnd as HWND, &mut point);
+                    ClientToScreen(hwnd as HWND, &mut origin);
                 }
-                point
             }
+            Rect::new(
+                origin.x as f64,
+                origin.y as f64,
+                p.width.unwrap_or(0.0) * scale_factor,
+                p.height.unwrap_or(0.0) * scale_factor,
+            )
         }
         None => {
             // Get the current cursor position using GetCursorPos
@@ -185,40 +492,135 @@ pub fn show_context_menu<R: Runtime>(
             unsafe {
                 GetCursorPos(&mut current_pos);
             }
-            current_pos
+            Rect::new(current_pos.x as f64, current_pos.y as f64, 0.0, 0.0)
         }
     };
+    let (x, y) = Placement {
+        offset_x: placement.offset_x * scale_factor,
+        offset_y: placement.offset_y * scale_factor,
+        ..placement
+    }
+    .anchor_point(&anchor);
+
+    // Windows places the menu anchor on the point with the alignment flags,
+    // right-to-left menus are mirrored as well
+    let (menu_x, menu_y) = placement.menu_anchor.factors();
+    let mut flags = TPM_RIGHTBUTTON
+        | match menu_x {
+            x if x < 0.5 => TPM_LEFTALIGN,
+            x if x > 0.5 => TPM_RIGHTALIGN,
+            _ => TPM_CENTERALIGN,
+        }
+        | match menu_y {
+            y if y < 0.5 => TPM_TOPALIGN,
+            y if y > 0.5 => TPM_BOTTOMALIGN,
+            _ => TPM_VCENTERALIGN,
+        };
+    if direction == Direction::Rtl {
+        flags |= TPM_LAYOUTRTL;
+    }
+
+    // Flip the menu by keeping it out of the anchor rectangle,
+    // Windows always slides the menu to keep it on the screen
+    let mut params = TPMPARAMS {
+        cbSize: std::mem::size_of::<TPMPARAMS>() as u32,
+        rcExclude: RECT {
+            left: anchor.x as i32,
+            top: anchor.y as i32,
+            right: (anchor.x + anchor.width) as i32,
+            bottom: (anchor.y + anchor.height) as i32,
+        },
+    };
+    let (_, anchor_y) = placement.rect_anchor.factors();
+    if anchor_y != 0.5 {
+        // Prefer moving the menu above or below the anchor
+        flags |= TPM_VERTICAL;
+    }
+    let params_ptr = if placement.flip && (anchor.width > 0.0 || anchor.height > 0.0) {
+        &mut params as *mut TPMPARAMS
+    } else {
+        null_mut()
+    };
 
+    OPEN_MENUS.lock().unwrap().insert(
+        window.label().to_string(),
+        OpenMenu {
+            hwnd: hwnd as usize,
+            menu: menu as usize,
+            reason: CloseReason::User,
+            destroy,
+        },
+    );
     unsafe {
-        TrackPopupMenu(
-            menu,
-            TPM_LEFTALIGN | TPM_TOPALIGN | TPM_RIGHTBUTTON,
-            position.x,
-            position.y,
-            0, // reserved param
-            hwnd as HWND,
-            std::ptr::null_mut(),
-        );
-
-        DestroyMenu(menu);
+        // Select the first item like menus opened from the keyboard do,
+        // the key press is handled once the menu loop starts
+        if trigger == Trigger::Keyboard {
+            PostMessageW(hwnd as HWND, WM_KEYDOWN, VK_DOWN as WPARAM, 0);
+        }
+        loop {
+            let command = TrackPopupMenuEx(
+                menu,
+                flags | TPM_RETURNCMD,
+                x as i32,
+                y as i32,
+                hwnd as HWND,
+                params_ptr,
+            ) as u32;
+            match toggles.get(&command) {
+                // Toggle the item, emit its event and show the menu again where it was
+                Some((item_menu, position)) => {
+                    let item_menu = *item_menu as HMENU;
+                    let checked = GetMenuState(item_menu, *position, MF_BYPOSITION) & MF_CHECKED;
+                    let state = if checked == 0 {
+                        MF_CHECKED
+                    } else {
+                        MF_UNCHECKED
+                    };
+                    CheckMenuItem(item_menu, *position, MF_BYPOSITION | state);
+                    handle_menu_item_click(command, window.clone());
+                }
+                None => {
+                    // Handle other items before the menu-did-close event, as on the other platforms
+                    if command != 0 {
+                        handle_menu_item_click(command, window.clone());
+                    }
+                    break;
+                }
+            }
+        }
+    }
+
+    // The items can't be updated once the menu is closed, it may be destroyed
+    ITEMS.lock().unwrap().clear();
+
+    // Menus created with `create_menu` are kept unless destroyed while shown
+    let (reason, destroy) = OPEN_MENUS
+        .lock()
+        .unwrap()
+        .remove(window.label())
+        .map_or((CloseReason::User, destroy), |open_menu| {
+            (open_menu.reason, open_menu.destroy)
+        });
+
+    unsafe {
+        if destroy {
+            DestroyMenu(menu);
+        }
 
         // Post a quit message to exit the message loop
         PostQuitMessage(0);
     }
 
     // Emit the menu-did-close event to JavaScript
-    window.emit("menu-did-close", ()).unwrap();
+    window
+        .emit("menu-did-close", MenuClosed::new(reason))
+        .unwrap();
 
     let mut msg: MSG = unsafe { std::mem::zeroed() };
     while unsafe { GetMessageW(&mut msg, null_mut(), 0, 0) } > 0 {
         match msg.message {
-            WM_COMMAND => {
-                // Extract the menu item ID from wParam
-                let menu_item_id = LOWORD(msg.wParam as u32);
-                handle_menu_item_click(menu_item_id.into(), window.clone());
-            }
             WM_ACTIVATE => {
-                if LOWORD(msg.wParam as u32) == WA_INACTIVE {
+                if destroy && LOWORD(msg.wParam as u32) == WA_INACTIVE {
                     unsafe { DestroyMenu(menu) };
                 }
             }